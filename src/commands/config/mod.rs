@@ -0,0 +1,70 @@
+use clap::Args;
+
+use crate::commands::error::CommandError;
+use crate::config::Manager;
+use crate::diff::ContextSize;
+
+#[derive(Args)]
+pub struct ConfigCommand {
+    /// 差分で変更行の前後に表示する行数の既定値 (full でファイル全体)
+    #[arg(short = 'U', long, value_name = "LINES")]
+    pub context: Option<ContextSize>,
+}
+
+pub fn handle(cmd: ConfigCommand, config: &mut dyn Manager) -> Result<(), CommandError> {
+    let mut data = config.load()?;
+
+    let Some(context) = cmd.context else {
+        println!("context: {}", data.context());
+        return Ok(());
+    };
+
+    data.set_context(context);
+    config.save(&data)?;
+
+    println!("context を '{}' に設定しました", context);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::ConfigData;
+    use crate::config::MockManager;
+
+    use super::*;
+
+    #[test]
+    fn test_handle_config_with_context_option() {
+        let mut mock_manager = MockManager::new();
+        mock_manager.expect_load().returning(|| {
+            let mut data = ConfigData::default();
+            data.set_base_branch("main".to_string()).unwrap();
+            Ok(data)
+        });
+        mock_manager
+            .expect_save()
+            .withf(|data| {
+                data.context() == ContextSize::Full
+                    && data.base_branch() == Some("main".to_string())
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        let cmd = ConfigCommand {
+            context: Some(ContextSize::Full),
+        };
+
+        assert!(handle(cmd, &mut mock_manager).is_ok());
+    }
+
+    #[test]
+    fn test_handle_config_without_option_does_not_save() {
+        let mut mock_manager = MockManager::new();
+        mock_manager
+            .expect_load()
+            .returning(|| Ok(ConfigData::default()));
+        mock_manager.expect_save().never();
+        let cmd = ConfigCommand { context: None };
+
+        assert!(handle(cmd, &mut mock_manager).is_ok());
+    }
+}
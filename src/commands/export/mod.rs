@@ -0,0 +1,209 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::commands::error::CommandError;
+use crate::commands::files::{self, FileDiff};
+use crate::config::{Manager, ValidatedConfigData};
+use crate::diff::{ContextSize, HtmlFile, HtmlReport, PatchTarget};
+use crate::git::{GitError, GitProvider};
+
+#[derive(Args)]
+pub struct ExportCommand {
+    /// 書き出す HTML ファイルのパス
+    #[arg(long, value_name = "PATH")]
+    pub html: PathBuf,
+
+    /// 変更されたすべてのファイルではなく、設定済みのスコープのファイルだけを書き出す
+    #[arg(long)]
+    pub scope: bool,
+
+    /// 変更行の前後に含める行数 (full でファイル全体)。未指定なら設定値を使う
+    #[arg(short = 'U', long, value_name = "LINES")]
+    pub context: Option<ContextSize>,
+
+    /// ベースブランチの先端ではなく、分岐したコミット (マージベース) と比較する (git diff A...B)
+    #[arg(long)]
+    pub merge_base: bool,
+}
+
+/// 旧側と新側のパスの組ごとに、ファイル全体の差分を HTML にする
+pub fn html_files(
+    git: &dyn GitProvider,
+    (old_revision, target): (&str, &str),
+    paths: &[(String, String)],
+    context: ContextSize,
+) -> Result<Vec<HtmlFile>, GitError> {
+    paths
+        .iter()
+        .map(|(old_path, new_path)| {
+            let path = path_label(old_path, new_path);
+            let file =
+                match files::load_paths_diff(git, (old_revision, old_path), (target, new_path))? {
+                    FileDiff::Text(diff, _) => {
+                        let diff = diff.with_context(context);
+                        let old = PatchTarget {
+                            path: old_path,
+                            start: 1,
+                        };
+                        let new = PatchTarget {
+                            path: new_path,
+                            start: 1,
+                        };
+                        HtmlFile {
+                            path,
+                            summary: diff.stats().to_string(),
+                            body: diff.html(&old, &new),
+                        }
+                    }
+                    FileDiff::Binary(change) => HtmlFile::note(
+                        path,
+                        change.to_string(),
+                        "バイナリファイルのため差分を表示できません",
+                    ),
+                };
+            Ok(file)
+        })
+        .collect()
+}
+
+/// 見出しに出すパス。旧側と新側で異なれば両方を並べる
+pub fn path_label(old_path: &str, new_path: &str) -> String {
+    if old_path == new_path {
+        new_path.to_string()
+    } else {
+        format!("{} → {}", old_path, new_path)
+    }
+}
+
+pub fn handle(
+    cmd: ExportCommand,
+    config: &mut dyn Manager,
+    git: &dyn GitProvider,
+) -> Result<(), CommandError> {
+    let data = config.load()?;
+    let context = cmd.context.unwrap_or(data.context());
+
+    let (base, target, scope_paths) = if cmd.scope {
+        let data: ValidatedConfigData = data.try_into()?;
+        let paths = vec![(data.old_file_path().clone(), data.new_file_path().clone())];
+        (
+            data.base_branch().clone(),
+            data.current_branch().clone(),
+            Some(paths),
+        )
+    } else {
+        let base = data.base_branch().ok_or_else(|| {
+            CommandError::InvalidInput(
+                "base_branchが設定されていません。mirudi init を先に実行してください".to_string(),
+            )
+        })?;
+        (base, git.get_current_branch()?, None)
+    };
+
+    let (old_revision, title) = if cmd.merge_base {
+        let merge_base = git.merge_base(&base, &target)?;
+        (merge_base, format!("{}...{}", base, target))
+    } else {
+        (base.clone(), format!("{}..{}", base, target))
+    };
+
+    // マージベースと比較するなら、ベースブランチ側だけで進んだ変更のファイルは含めない
+    let paths = match scope_paths {
+        Some(paths) => paths,
+        None => git
+            .list_changed_files(&old_revision, &target)?
+            .into_iter()
+            .map(|path| (path.clone(), path))
+            .collect(),
+    };
+
+    let report = HtmlReport {
+        title,
+        files: html_files(git, (&old_revision, &target), &paths, context)?,
+    };
+    fs::write(&cmd.html, report.render())?;
+    println!("{} に書き出しました", cmd.html.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigData, MockManager};
+    use crate::git::BlobInfo;
+    use crate::git::core::{MockGitProvider, SourceKind};
+    use crate::utils::test_support::text_of;
+    use mockall::predicate::eq;
+
+    fn blob(_: &str, path: &str, _: Option<SourceKind>) -> Result<BlobInfo, GitError> {
+        Ok(BlobInfo {
+            oid: format!("{:0>40}", path.len()),
+            size: path.len(),
+            binary: path.ends_with(".png"),
+        })
+    }
+
+    #[test]
+    fn renders_each_pair_of_paths() {
+        let mut git = MockGitProvider::new();
+        git.expect_blob_info().returning(blob);
+        git.expect_extract_text()
+            .returning(|branch, path, _| match (branch, path) {
+                ("main", "old.rs") => text_of(&["fn a() {}", "fn b() {}"]),
+                ("feature", "new.rs") => text_of(&["fn a() {}", "fn c() {}"]),
+                _ => Err(GitError::FileNotFound),
+            });
+        let paths = vec![
+            ("old.rs".to_string(), "new.rs".to_string()),
+            ("logo.png".to_string(), "logo.png".to_string()),
+        ];
+
+        let files = html_files(&git, ("main", "feature"), &paths, ContextSize::default()).unwrap();
+
+        assert_eq!(files[0].path, "old.rs → new.rs");
+        assert!(files[0].body.contains("<tr class=\"added\">"));
+        assert_eq!(files[1].path, "logo.png");
+        assert!(files[1].summary.starts_with("バイナリファイル"));
+    }
+
+    #[test]
+    fn merge_base_lists_changes_from_merge_base() {
+        let mut config = MockManager::new();
+        config.expect_load().returning(|| {
+            let mut data = ConfigData::default();
+            data.set_base_branch("main".to_string()).unwrap();
+            Ok(data)
+        });
+        let mut git = MockGitProvider::new();
+        git.expect_get_current_branch()
+            .returning(|| Ok("feature".to_string()));
+        git.expect_merge_base()
+            .with(eq("main"), eq("feature"))
+            .returning(|_, _| Ok("abc123".to_string()));
+        git.expect_list_changed_files()
+            .with(eq("abc123"), eq("feature"))
+            .returning(|_, _| Ok(vec!["a.rs".to_string()]));
+        git.expect_blob_info().returning(blob);
+        git.expect_extract_text()
+            .returning(|branch, _, _| match branch {
+                "abc123" => text_of(&["fn a() {}"]),
+                _ => text_of(&["fn b() {}"]),
+            });
+        let html = std::env::temp_dir().join("mirudi-export-merge-base.html");
+        let cmd = ExportCommand {
+            html: html.clone(),
+            scope: false,
+            context: None,
+            merge_base: true,
+        };
+
+        handle(cmd, &mut config, &git).unwrap();
+        let report = fs::read_to_string(&html).unwrap();
+        fs::remove_file(&html).unwrap();
+
+        assert!(report.contains("<title>main...feature</title>"));
+        assert!(report.contains("a.rs"));
+    }
+}
@@ -0,0 +1,22 @@
+use std::fmt;
+
+use clap::ValueEnum;
+
+/// --find で似た箇所を探す対象
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+pub enum FindScope {
+    /// 対象ブランチ側のファイルだけ
+    File,
+    /// ベースブランチから変更されたすべてのファイル
+    Changed,
+}
+
+impl fmt::Display for FindScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            FindScope::File => "file",
+            FindScope::Changed => "changed",
+        };
+        write!(f, "{}", text)
+    }
+}
@@ -0,0 +1,28 @@
+use std::fmt;
+
+use clap::ValueEnum;
+
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Patch,
+    /// 差分と取り出し元の情報を JSON で出力する
+    Json,
+    /// CSS と構文の色付けを埋め込んだ HTML を出力する
+    Html,
+    /// PR のコメントに貼れる ```diff ブロックの Markdown を出力する
+    Markdown,
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Patch => "patch",
+            OutputFormat::Json => "json",
+            OutputFormat::Html => "html",
+            OutputFormat::Markdown => "markdown",
+        };
+        write!(f, "{}", text)
+    }
+}
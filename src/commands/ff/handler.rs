@@ -1,111 +1,688 @@
 use super::DiffMode;
 use super::FFCommand;
+use super::FindScope;
+use super::Layout;
+use super::OutputFormat;
 use super::Range;
+use super::RangeSpec;
+use super::Selection;
+use super::markdown::MarkdownReport;
+use super::report::{DiffReport, ReportTarget};
+use super::selection::SEPARATOR;
+
+use once_cell::unsync::OnceCell;
 
 use crate::config::ValidatedConfigData;
-use crate::diff::{Diff, DiffProvider};
-use crate::git::{GitProvider, core::SourceKind};
+use crate::diff::{
+    Diff, DiffPiece, DiffProvider, HtmlFile, HtmlReport, IgnorePatterns, Overflow, PiecewiseDiff,
+    RegionMatch, find_region,
+};
+use crate::git::{GitError, GitProvider, core::SourceKind};
 
 use crate::commands::error::CommandError;
+use crate::commands::export;
+use crate::commands::files::{self, BinaryChange};
 
 pub struct DiffHandler<'a> {
     cmd: FFCommand,
     git: &'a dyn GitProvider,
     data: ValidatedConfigData,
+    /// 行番号に直した旧側と新側の範囲。内容から探す指定はファイルを読むため、一度だけ求める
+    ranges: OnceCell<(Vec<Range>, Vec<Range>)>,
+    /// ベースブランチと対象ブランチのマージベース。旧側や見出しで何度も使うため、一度だけ求める
+    merge_base: OnceCell<String>,
+    /// --find で見つけた、新側のファイルと範囲
+    found: Option<(String, Range)>,
 }
 
 impl<'a> DiffHandler<'a> {
     pub fn build(cmd: FFCommand, git: &'a dyn GitProvider, data: ValidatedConfigData) -> Self {
-        Self { cmd, git, data }
+        Self {
+            cmd,
+            git,
+            data,
+            ranges: OnceCell::new(),
+            merge_base: OnceCell::new(),
+            found: None,
+        }
     }
 }
 
 impl DiffHandler<'_> {
     pub fn exec(&mut self) -> Result<(), CommandError> {
         self.validate_source()?;
+        self.validate_layout()?;
+        if let Some(scope) = self.cmd.find.clone() {
+            self.found = Some(self.find_region(&scope)?);
+        }
+        if let Some(change) = self.binary_change()? {
+            match self.cmd.format {
+                OutputFormat::Json => println!("{}", self.json_report(None, Some(change))?),
+                OutputFormat::Html => {
+                    let file = HtmlFile::note(
+                        self.path_label(),
+                        change.to_string(),
+                        "バイナリファイルのため差分を表示できません",
+                    );
+                    print!("{}", self.html_report(file)?);
+                }
+                _ => println!("{}", change),
+            }
+            return Ok(());
+        }
         let old_lines = self.extract_old_lines()?;
         let new_lines = self.extract_new_lines()?;
         let diff_result = self.generate_diff(old_lines, new_lines)?;
-        self.display_diff(diff_result);
-        Ok(())
+        self.display_diff(diff_result)
     }
 
+    /// 作業ツリーから読めるのは、チェックアウトしているブランチの側だけ
     fn validate_source(&self) -> Result<(), CommandError> {
         let git_branch = self.git.get_current_branch()?;
 
-        if self.cmd.source == SourceKind::Worktree && *self.data.current_branch() != git_branch {
+        if self.cmd.new_source == SourceKind::Worktree && *self.data.current_branch() != git_branch
+        {
             return Err(CommandError::InvalidInput(format!(
-                "比較するブランチが現在のブランチと異なる場合、--source=worktree を指定することはできません。現在のブランチ: {}, 指定されたブランチ: {}",
+                "比較するブランチが現在のブランチと異なる場合、--new-source=worktree を指定することはできません。現在のブランチ: {}, 指定されたブランチ: {}",
                 self.data.current_branch(),
                 git_branch
             )));
         }
+        if self.cmd.old_source == SourceKind::Worktree {
+            if self.cmd.merge_base {
+                return Err(CommandError::InvalidInput(
+                    "--merge-base では旧側をマージベースのコミットから取り出すため、--old-source=worktree は指定できません"
+                        .to_string(),
+                ));
+            }
+            if *self.data.base_branch() != git_branch {
+                return Err(CommandError::InvalidInput(format!(
+                    "ベースブランチが現在のブランチと異なる場合、--old-source=worktree を指定することはできません。現在のブランチ: {}, ベースブランチ: {}",
+                    git_branch,
+                    self.data.base_branch()
+                )));
+            }
+        }
         Ok(())
     }
 
-    fn extract_old_lines(&self) -> Result<Vec<String>, CommandError> {
-        let branch = self.data.base_branch();
-        let file_path = self.data.old_file_path();
-        let range: Range = Range::parse(&self.cmd.old_range)?;
-
-        let lines = self.git.extract_lines(
-            branch,
-            file_path,
-            range.start(),
-            range.end(),
-            Some(self.cmd.source.clone()),
+    /// 3 列表示は端末に出すテキストでしか描画できない
+    fn validate_layout(&self) -> Result<(), CommandError> {
+        if self.cmd.layout == Layout::ThreeWay && self.cmd.format != OutputFormat::Text {
+            return Err(CommandError::InvalidInput(
+                "--layout three-way は --format text でのみ指定できます".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 旧側に使うリビジョン。--merge-base ならベースブランチとの分岐点になる。
+    /// 3 列表示ではマージベースを別の列に出すため、旧側はベースブランチの先端のまま
+    fn old_revision(&self) -> Result<String, CommandError> {
+        if self.cmd.merge_base && self.cmd.layout != Layout::ThreeWay {
+            return self.merge_base();
+        }
+        Ok(self.data.base_branch().clone())
+    }
+
+    fn merge_base(&self) -> Result<String, CommandError> {
+        let merge_base = self.merge_base.get_or_try_init(|| {
+            self.git
+                .merge_base(self.data.base_branch(), self.data.current_branch())
+        })?;
+        Ok(merge_base.clone())
+    }
+
+    /// バイナリファイルは行を取り出せないため、大きさと OID の変化だけを表示する
+    fn binary_change(&self) -> Result<Option<BinaryChange>, CommandError> {
+        let change = files::binary_change(
+            self.git,
+            (&self.old_revision()?, self.data.old_file_path()),
+            (self.data.current_branch(), self.new_file_path()),
+            (self.cmd.old_source.clone(), self.cmd.new_source.clone()),
         )?;
+        Ok(change)
+    }
 
-        Ok(lines)
+    /// 旧側と新側の範囲。内容から探す指定は、それぞれのブランチのファイルで別々に行番号に直す。
+    /// 新側を省略した場合は、旧側の範囲を対象ブランチのファイルへ対応付ける
+    fn ranges(&self) -> Result<(&[Range], &[Range]), CommandError> {
+        self.ranges
+            .get_or_try_init(|| {
+                let old = self.resolve_range(
+                    &self.cmd.old_range,
+                    &self.old_revision()?,
+                    self.data.old_file_path(),
+                    self.cmd.old_source.clone(),
+                )?;
+                let new = match (&self.cmd.new_range, &self.found) {
+                    (Some(spec), _) => self.resolve_range(
+                        spec,
+                        self.data.current_branch(),
+                        self.new_file_path(),
+                        self.cmd.new_source.clone(),
+                    )?,
+                    (None, Some((_, range))) => vec![*range],
+                    (None, None) => self.map_ranges(&old)?,
+                };
+                Ok((old, new))
+            })
+            .map(|(old, new)| (old.as_slice(), new.as_slice()))
+    }
+
+    fn resolve_range(
+        &self,
+        spec: &str,
+        revision: &str,
+        file_path: &str,
+        source: SourceKind,
+    ) -> Result<Vec<Range>, CommandError> {
+        let spec = RangeSpec::parse(spec)?;
+        if let Some(ranges) = spec.lines() {
+            return Ok(ranges);
+        }
+        let text = self.git.extract_text(revision, file_path, Some(source))?;
+        spec.resolve(&text.lines, file_path)
     }
 
-    fn extract_new_lines(&self) -> Result<Vec<String>, CommandError> {
+    /// 新側のファイル。--find で別のファイルから見つけた場合はそのファイルになる
+    fn new_file_path(&self) -> &str {
+        self.found
+            .as_ref()
+            .map_or(self.data.new_file_path(), |(path, _)| path)
+    }
+
+    /// 旧側の範囲の内容にもっとも似た箇所を、対象ブランチのファイルから探す。
+    /// 見つけた場所と類似度は、出力の妨げにならないよう標準エラーに出す
+    fn find_region(&self, scope: &FindScope) -> Result<(String, Range), CommandError> {
+        let old_revision = self.old_revision()?;
+        let ranges = self.resolve_range(
+            &self.cmd.old_range,
+            &old_revision,
+            self.data.old_file_path(),
+            self.cmd.old_source.clone(),
+        )?;
+        // 見つけるのは 1 つの範囲なので、範囲ごとに比較する旧側も 1 つに限る
+        if ranges.len() > 1 {
+            return Err(CommandError::InvalidInput(
+                "--find では OLD_FILE_RANGE に範囲を 1 つだけ指定してください".to_string(),
+            ));
+        }
+        let needle = self
+            .extract_selection(
+                &old_revision,
+                self.data.old_file_path(),
+                &ranges,
+                self.cmd.old_source.clone(),
+            )?
+            .into_lines();
+
         let branch = self.data.current_branch();
-        let file_path = self.data.new_file_path();
-        let range = Range::parse(&self.cmd.new_range)?;
-
-        let lints = self.git.extract_lines(
-            branch,
-            file_path,
-            range.start(),
-            range.end(),
-            Some(self.cmd.source.clone()),
+        let paths = match scope {
+            FindScope::File => vec![self.data.new_file_path().clone()],
+            // --merge-base では、比較する旧側と同じくマージベースからの変更を探す
+            FindScope::Changed => self.git.list_changed_files(&old_revision, branch)?,
+        };
+
+        let mut best: Option<(String, RegionMatch)> = None;
+        for path in paths {
+            // 削除されたファイルやバイナリファイルは探さない
+            let source = Some(self.cmd.new_source.clone());
+            match self.git.blob_info(branch, &path, source.clone()) {
+                Ok(blob) if !blob.binary => {}
+                _ => continue,
+            }
+            // 文字コードを判定できないファイルなども、1 つのために探索全体を止めず飛ばす
+            let text = match self.git.extract_text(branch, &path, source) {
+                Ok(text) => text,
+                Err(e) => {
+                    eprintln!("{} を読めないため飛ばします: {}", path, e);
+                    continue;
+                }
+            };
+            if let Some(found) = find_region(&needle, &text.lines)
+                && best
+                    .as_ref()
+                    .is_none_or(|(_, best)| found.score > best.score)
+            {
+                best = Some((path, found));
+            }
+        }
+
+        let (path, found) = best.ok_or_else(|| {
+            CommandError::InvalidInput(format!("{} に旧側の範囲と似た箇所が見つかりません", branch))
+        })?;
+        eprintln!(
+            "{} の L{}-L{} がもっとも似ています (類似度 {:.0}%)",
+            path,
+            found.start,
+            found.end,
+            found.score * 100.0
+        );
+        let range = Range::new(found.start, found.end)?;
+        Ok((path, range))
+    }
+
+    /// git log -L と同じく、ファイル全体の差分をたどって旧側の範囲が新側のどこに移ったかを求める。
+    /// 対応付けた範囲と一致した行数は、出力の妨げにならないよう標準エラーに出す
+    fn map_ranges(&self, old: &[Range]) -> Result<Vec<Range>, CommandError> {
+        let old_revision = self.old_revision()?;
+        let old_text = self.git.extract_text(
+            &old_revision,
+            self.data.old_file_path(),
+            Some(self.cmd.old_source.clone()),
         )?;
-        Ok(lints)
+        let new_text = self.git.extract_text(
+            self.data.current_branch(),
+            self.new_file_path(),
+            Some(self.cmd.new_source.clone()),
+        )?;
+        let diff = Diff::new(old_text.lines, new_text.lines)
+            .with_algorithm(self.cmd.algorithm)
+            .with_whitespace(self.cmd.whitespace.mode())
+            .with_ignore_cr_at_eol(self.cmd.whitespace.ignore_cr_at_eol);
+
+        old.iter()
+            .map(|range| {
+                let mapping = diff.map_lines(range.start(), range.end()).ok_or_else(|| {
+                    CommandError::InvalidInput(format!(
+                        "{} の L{}-L{} は {} ですべて削除されているため、対応する範囲がありません",
+                        self.data.old_file_path(),
+                        range.start(),
+                        range.end(),
+                        self.data.current_branch()
+                    ))
+                })?;
+                eprintln!(
+                    "L{}-L{} を {} の {} に対応付けました",
+                    range.start(),
+                    range.end(),
+                    self.data.current_branch(),
+                    mapping
+                );
+                if !mapping.is_confident() {
+                    eprintln!(
+                        "一致する行が少ないため、対応付けが正しいか確認してください。NEW_FILE_RANGE で範囲を指定することもできます"
+                    );
+                }
+                Range::new(mapping.start, mapping.end)
+            })
+            .collect()
+    }
+
+    fn extract_old_lines(&self) -> Result<Selection, CommandError> {
+        let branch = self.old_revision()?;
+        let file_path = self.data.old_file_path();
+        let (ranges, _) = self.ranges()?;
+
+        self.extract_selection(&branch, file_path, ranges, self.cmd.old_source.clone())
+    }
+
+    fn extract_new_lines(&self) -> Result<Selection, CommandError> {
+        let branch = self.data.current_branch();
+        let file_path = self.new_file_path();
+        let (_, ranges) = self.ranges()?;
+
+        self.extract_selection(branch, file_path, ranges, self.cmd.new_source.clone())
+    }
+
+    /// 範囲ごとに行を取り出す
+    fn extract_selection(
+        &self,
+        branch: &str,
+        file_path: &str,
+        ranges: &[Range],
+        source: SourceKind,
+    ) -> Result<Selection, CommandError> {
+        let pieces = ranges
+            .iter()
+            .map(|range| {
+                let lines = self.git.extract_lines(
+                    branch,
+                    file_path,
+                    range.start(),
+                    range.end(),
+                    Some(source.clone()),
+                )?;
+                Ok(lines)
+            })
+            .collect::<Result<Vec<_>, CommandError>>()?;
+        Ok(Selection::new(pieces))
+    }
+
+    /// マージベースは常にコミットなので、--old-source に関わらずコミットから取り出す。
+    /// 内容から探す範囲は、マージベースのファイルで改めて行番号に直す
+    fn extract_merge_base_lines(&self) -> Result<Selection, CommandError> {
+        let merge_base = self.merge_base()?;
+        let file_path = self.data.old_file_path();
+        let lines = self
+            .resolve_range(
+                &self.cmd.old_range,
+                &merge_base,
+                file_path,
+                SourceKind::Commit,
+            )
+            .and_then(|ranges| {
+                self.extract_selection(&merge_base, file_path, &ranges, SourceKind::Commit)
+            });
+
+        // ブランチの分岐後に作られたファイルは、マージベースでは空として扱う
+        match lines {
+            Err(CommandError::Git(GitError::FileNotFound)) => Ok(Selection::from(vec![])),
+            lines => lines,
+        }
     }
 
     fn generate_diff(
         &self,
-        old_lines: Vec<String>,
-        new_lines: Vec<String>,
+        old: impl Into<Selection>,
+        new: impl Into<Selection>,
     ) -> Result<String, CommandError> {
-        let diff = Diff::new(old_lines, new_lines);
+        let (old, new) = (old.into(), new.into());
+        if old.pieces.len() != new.pieces.len() {
+            return Err(CommandError::InvalidInput(format!(
+                "旧側と新側で範囲の数が異なります ({} 個と {} 個)。離れた範囲は前から順に 1 つずつ比較するため、同じ数だけ指定してください",
+                old.pieces.len(),
+                new.pieces.len()
+            )));
+        }
+        let split = old.is_split();
+        if split && self.cmd.format == OutputFormat::Patch {
+            return Err(CommandError::InvalidInput(
+                "複数の範囲の差分は git apply できないため、--format patch は指定できません"
+                    .to_string(),
+            ));
+        }
+        if split && self.cmd.suggestion {
+            return Err(CommandError::InvalidInput(
+                "複数の範囲を指定した場合、--suggestion は指定できません".to_string(),
+            ));
+        }
+
+        let suggestion = (self.cmd.suggestion && self.cmd.format == OutputFormat::Markdown)
+            .then(|| new.pieces[0].clone());
+        let starts = self.starts()?;
+        let merge_base = match self.cmd.layout {
+            Layout::ThreeWay if self.cmd.format == OutputFormat::Text => {
+                Some(self.extract_merge_base_lines()?)
+            }
+            _ => None,
+        };
+        let pieces = old
+            .pieces
+            .into_iter()
+            .zip(new.pieces)
+            .zip(starts)
+            .map(|((old, new), (old_start, new_start))| {
+                Ok(DiffPiece {
+                    diff: self.build_diff(old, new)?,
+                    old_start,
+                    new_start,
+                })
+            })
+            .collect::<Result<Vec<_>, CommandError>>()?;
+        let diff = PiecewiseDiff::new(pieces);
+
+        if self.cmd.stat && self.cmd.format != OutputFormat::Json {
+            return Ok(diff.stats().to_string());
+        }
+
+        let overflow = if self.cmd.wrap {
+            Overflow::Wrap
+        } else {
+            Overflow::Truncate
+        };
 
-        let diff_result = match self.cmd.mode {
-            DiffMode::Slice => diff.slice(),
-            DiffMode::Words => diff.words(),
-            DiffMode::Lines => diff.lines(),
-            DiffMode::Chars => diff.chars(),
+        let diff_result = match self.cmd.format {
+            OutputFormat::Text => {
+                let width = terminal_width();
+                self.render_pieces(&diff, |i, piece| match (&self.cmd.layout, &self.cmd.mode) {
+                    (Layout::SideBySide, _) => piece.diff.side_by_side(width, overflow),
+                    (Layout::ThreeWay, _) => {
+                        let merge_base = merge_base
+                            .as_ref()
+                            .and_then(|merge_base| merge_base.pieces.get(i))
+                            .cloned()
+                            .unwrap_or_default();
+                        piece
+                            .diff
+                            .three_way(merge_base)
+                            .side_by_side(width, overflow)
+                    }
+                    (Layout::Inline, DiffMode::Slice) => piece.diff.slice(),
+                    (Layout::Inline, DiffMode::Words) => piece.diff.words(),
+                    (Layout::Inline, DiffMode::Lines) => piece.diff.lines(),
+                    (Layout::Inline, DiffMode::Chars) => piece.diff.chars(),
+                    (Layout::Inline, DiffMode::Hunks) => {
+                        piece.diff.lines_in_hunks(piece.old_start, piece.new_start)
+                    }
+                })?
+                // CRLF の `\r` をそのまま出すと行頭に戻って表示が崩れるため、見える記号にする
+                .replace('\r', "␍")
+            }
+            OutputFormat::Patch => diff.unified(self.data.old_file_path(), self.new_file_path()),
+            OutputFormat::Json => self.json_report(Some(&diff), None)?,
+            OutputFormat::Html => {
+                let file = HtmlFile {
+                    path: self.path_label(),
+                    summary: diff.stats().to_string(),
+                    body: diff.html(self.data.old_file_path(), self.new_file_path()),
+                };
+                self.html_report(file)?
+            }
+            OutputFormat::Markdown => {
+                let (old_target, new_target) = self.report_targets()?;
+                let report = MarkdownReport {
+                    revisions: self.revision_range()?,
+                    old: old_target,
+                    new: new_target,
+                    patch: diff.unified(self.data.old_file_path(), self.new_file_path()),
+                    suggestion,
+                };
+                report.render()
+            }
         };
 
         Ok(diff_result)
     }
 
-    fn display_diff(&self, diff_result: String) {
+    /// 比較の設定を反映した、1 つの範囲の差分
+    fn build_diff(&self, old: Vec<String>, new: Vec<String>) -> Result<Diff, CommandError> {
+        Ok(Diff::new(old, new)
+            .with_algorithm(self.cmd.algorithm)
+            .with_whitespace(self.cmd.whitespace.mode())
+            .with_ignore_blank_lines(self.cmd.whitespace.ignore_blank_lines)
+            .with_ignore_cr_at_eol(self.cmd.whitespace.ignore_cr_at_eol)
+            .with_ignore_patterns(self.ignore_patterns()?)
+            .with_context(self.cmd.context.unwrap_or(self.data.context())))
+    }
+
+    /// 範囲ごとに描画し、複数あれば範囲の行番号を示す区切りを挟んで並べる
+    fn render_pieces(
+        &self,
+        diff: &PiecewiseDiff,
+        render: impl Fn(usize, &DiffPiece) -> String,
+    ) -> Result<String, CommandError> {
+        if let [piece] = diff.pieces() {
+            return Ok(render(0, piece));
+        }
+        let (old_ranges, new_ranges) = self.ranges()?;
+        let rendered = diff
+            .pieces()
+            .iter()
+            .zip(old_ranges.iter().zip(new_ranges))
+            .enumerate()
+            .map(|(i, (piece, (old, new)))| {
+                let label = format!(
+                    "{} L{}-L{} → L{}-L{}",
+                    SEPARATOR,
+                    old.start(),
+                    old.end(),
+                    new.start(),
+                    new.end()
+                );
+                format!("\x1b[2m{}\x1b[0m\n{}", label, render(i, piece))
+            })
+            .collect::<Vec<_>>();
+        Ok(rendered.join("\n"))
+    }
+
+    /// 設定の ignore_patterns と --ignore-matching を合わせたもの
+    fn ignore_patterns(&self) -> Result<IgnorePatterns, CommandError> {
+        let patterns = self
+            .data
+            .ignore_patterns()
+            .iter()
+            .chain(&self.cmd.whitespace.ignore_matching);
+        IgnorePatterns::new(patterns)
+            .map_err(|e| CommandError::InvalidInput(format!("無視するパターンが不正です: {}", e)))
+    }
+
+    /// 取り出した中での行番号をファイル全体での行番号に直すための、範囲ごとの旧側と新側の先頭行
+    fn starts(&self) -> Result<Vec<(usize, usize)>, CommandError> {
+        let (old, new) = self.ranges()?;
+        Ok(old
+            .iter()
+            .zip(new)
+            .map(|(old, new)| (old.start(), new.start()))
+            .collect())
+    }
+
+    fn path_label(&self) -> String {
+        export::path_label(self.data.old_file_path(), self.new_file_path())
+    }
+
+    /// 比較したブランチの範囲。--merge-base なら git と同じく `...` でつなぐ
+    fn revision_range(&self) -> Result<String, CommandError> {
+        let separator = if self.old_revision()? == *self.data.base_branch() {
+            ".."
+        } else {
+            "..."
+        };
+        Ok(format!(
+            "{}{}{}",
+            self.data.base_branch(),
+            separator,
+            self.data.current_branch()
+        ))
+    }
+
+    fn report_targets(&self) -> Result<(ReportTarget<'_>, ReportTarget<'_>), CommandError> {
+        let (old_ranges, new_ranges) = self.ranges()?;
+        let old = ReportTarget {
+            path: self.data.old_file_path(),
+            ranges: old_ranges.to_vec(),
+        };
+        let new = ReportTarget {
+            path: self.new_file_path(),
+            ranges: new_ranges.to_vec(),
+        };
+        Ok((old, new))
+    }
+
+    /// 1 ファイル分の HTML レポート
+    fn html_report(&self, file: HtmlFile) -> Result<String, CommandError> {
+        let report = HtmlReport {
+            title: self.revision_range()?,
+            files: vec![file],
+        };
+        Ok(report.render())
+    }
+
+    /// 取り出し元の情報と一緒に、ファイル全体での行番号にしたハンクを JSON にする
+    fn json_report(
+        &self,
+        diff: Option<&PiecewiseDiff>,
+        binary: Option<BinaryChange>,
+    ) -> Result<String, CommandError> {
+        let (old, new) = self.report_targets()?;
+        let old_revision = self.old_revision()?;
+
+        let hunks = diff.filter(|_| !self.cmd.stat).map(PiecewiseDiff::hunks);
+
+        let report = DiffReport {
+            base_branch: self.data.base_branch(),
+            target_branch: self.data.current_branch(),
+            merge_base: (old_revision != *self.data.base_branch()).then_some(old_revision),
+            old,
+            new,
+            mode: self.cmd.mode.to_string(),
+            stats: diff.map(PiecewiseDiff::stats),
+            hunks,
+            binary,
+        };
+        report.to_json()
+    }
+
+    fn display_diff(&self, diff_result: String) -> Result<(), CommandError> {
+        if self.cmd.stat || self.cmd.format == OutputFormat::Json {
+            println!("{}", diff_result);
+            return Ok(());
+        }
+
+        if matches!(
+            self.cmd.format,
+            OutputFormat::Patch | OutputFormat::Html | OutputFormat::Markdown
+        ) {
+            // そのまま git apply やブラウザ、PR のコメントに渡せるよう、装飾を付けずに出力する
+            print!("{}", diff_result);
+            return Ok(());
+        }
+
+        let label = match self.cmd.layout {
+            Layout::Inline => self.cmd.mode.to_string(),
+            Layout::SideBySide | Layout::ThreeWay => self.cmd.layout.to_string(),
+        };
+        println!("\x1b[2m{}\x1b[0m", self.revision_summary()?);
         println!(
             "\x1b[1;34m=== Diff Mode: {} ===\x1b[0m",
-            self.cmd.mode.to_string().to_uppercase()
+            label.to_uppercase()
         );
         println!("{}", diff_result);
         println!("\x1b[1;34m==============================\x1b[0m\n");
+        Ok(())
+    }
+
+    /// 比較したリビジョンを、解決したコミットの SHA と件名とともに表す。
+    /// タグや HEAD~N を指定した場合も、実際にどのコミットと比べたかが分かる
+    fn revision_summary(&self) -> Result<String, CommandError> {
+        let base = self.data.base_branch();
+        let old_revision = self.old_revision()?;
+        let old = if self.cmd.old_source == SourceKind::Worktree {
+            format!("{} の作業ツリー", base)
+        } else if old_revision == *base {
+            format!("{} ({})", base, self.git.resolve_revision(base)?)
+        } else {
+            let commit = self.git.resolve_revision(&old_revision)?;
+            format!("{} とのマージベース ({})", base, commit)
+        };
+
+        let current = self.data.current_branch();
+        let new = if self.cmd.new_source == SourceKind::Worktree {
+            format!("{} の作業ツリー", current)
+        } else {
+            format!("{} ({})", current, self.git.resolve_revision(current)?)
+        };
+
+        Ok(format!("{} → {}", old, new))
     }
 }
 
+/// 端末でなければ COLUMNS、それもなければ 120 桁とみなす
+fn terminal_width() -> usize {
+    terminal_size::terminal_size()
+        .map(|(terminal_size::Width(w), _)| w as usize)
+        .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+        .unwrap_or(120)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::commands::ff::scope_input::ScopeCommandInput;
+    use crate::commands::ff::whitespace_input::WhitespaceInput;
     use crate::config::{ConfigData, ConfigScopeInput, ValidatedConfigData};
+    use crate::diff::{ContextSize, DiffAlgorithm};
     use crate::git::GitError;
     use crate::git::core::{MockGitProvider, SourceKind};
     use mockall::predicate::eq;
@@ -131,20 +708,38 @@ mod tests {
         }
     }
 
+    /// 各テストで共通の FFCommand。テストごとに必要な項目だけ上書きして使う
+    fn cmd() -> FFCommand {
+        FFCommand {
+            scope: setup_scope_input(),
+            old_range: "1-10".to_string(),
+            new_range: Some("11-20".to_string()),
+            find: None,
+            old_source: SourceKind::Commit,
+            new_source: SourceKind::Commit,
+            mode: DiffMode::Lines,
+            algorithm: DiffAlgorithm::Myers,
+            whitespace: WhitespaceInput::default(),
+            format: OutputFormat::Text,
+            layout: Layout::Inline,
+            wrap: false,
+            context: None,
+            stat: false,
+            merge_base: false,
+            suggestion: false,
+        }
+    }
+
     mod validate_source {
         use super::*;
 
         #[test]
         fn returns_ok() {
             let mut git = MockGitProvider::new();
-            let scope = setup_scope_input();
             let data = setup_data();
             let cmd = FFCommand {
-                scope,
-                old_range: "1-10".to_string(),
-                new_range: "11-20".to_string(),
-                source: SourceKind::Worktree,
-                mode: DiffMode::Lines,
+                new_source: SourceKind::Worktree,
+                ..cmd()
             };
 
             git.expect_get_current_branch()
@@ -158,14 +753,10 @@ mod tests {
         #[test]
         fn returns_error() {
             let mut git = MockGitProvider::new();
-            let scope = setup_scope_input();
             let data = setup_data();
             let cmd = FFCommand {
-                scope,
-                old_range: "1-10".to_string(),
-                new_range: "11-20".to_string(),
-                source: SourceKind::Worktree,
-                mode: DiffMode::Lines,
+                new_source: SourceKind::Worktree,
+                ..cmd()
             };
 
             git.expect_get_current_branch()
@@ -175,6 +766,129 @@ mod tests {
             let result = handler.validate_source();
             assert!(result.is_err());
         }
+
+        #[test]
+        fn old_worktree_is_ok_on_base_branch() {
+            let mut git = MockGitProvider::new();
+            git.expect_get_current_branch()
+                .returning(|| Ok("main".to_string()));
+
+            let cmd = FFCommand {
+                old_source: SourceKind::Worktree,
+                ..cmd()
+            };
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+            assert!(handler.validate_source().is_ok());
+        }
+
+        #[test]
+        fn old_worktree_requires_base_branch_checked_out() {
+            let mut git = MockGitProvider::new();
+            git.expect_get_current_branch()
+                .returning(|| Ok("feature".to_string()));
+
+            let cmd = FFCommand {
+                old_source: SourceKind::Worktree,
+                ..cmd()
+            };
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+            let err = handler.validate_source().unwrap_err().to_string();
+            assert!(err.contains("--old-source=worktree"));
+            assert!(err.contains("ベースブランチ: main"));
+        }
+
+        #[test]
+        fn old_worktree_conflicts_with_merge_base() {
+            let mut git = MockGitProvider::new();
+            git.expect_get_current_branch()
+                .returning(|| Ok("main".to_string()));
+
+            let cmd = FFCommand {
+                old_source: SourceKind::Worktree,
+                merge_base: true,
+                ..cmd()
+            };
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+            let err = handler.validate_source().unwrap_err().to_string();
+            assert!(err.contains("--merge-base"));
+        }
+    }
+
+    mod validate_layout {
+        use super::*;
+
+        #[test]
+        fn three_way_requires_text_format() {
+            let git = MockGitProvider::new();
+            let cmd = FFCommand {
+                layout: Layout::ThreeWay,
+                format: OutputFormat::Json,
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+            let err = handler.validate_layout().unwrap_err().to_string();
+
+            assert!(err.contains("--layout three-way"));
+        }
+
+        #[test]
+        fn three_way_text_is_ok() {
+            let git = MockGitProvider::new();
+            let cmd = FFCommand {
+                layout: Layout::ThreeWay,
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+
+            assert!(handler.validate_layout().is_ok());
+        }
+    }
+
+    mod binary_change {
+        use super::*;
+        use crate::git::BlobInfo;
+
+        fn blob(size: usize, binary: bool) -> BlobInfo {
+            BlobInfo {
+                oid: format!("{:a<40}", size),
+                size,
+                binary,
+            }
+        }
+
+        #[test]
+        fn returns_summary_for_binary_file() {
+            let mut git = MockGitProvider::new();
+            git.expect_blob_info()
+                .with(eq("main"), eq("old_file.txt"), eq(Some(SourceKind::Commit)))
+                .returning(|_, _, _| Ok(blob(10, true)));
+            git.expect_blob_info()
+                .with(
+                    eq("feature"),
+                    eq("new_file.txt"),
+                    eq(Some(SourceKind::Commit)),
+                )
+                .returning(|_, _, _| Ok(blob(12, true)));
+
+            let handler = DiffHandler::build(cmd(), &git, setup_data());
+            let change = handler.binary_change().unwrap().unwrap();
+            assert_eq!(
+                change.to_string(),
+                "バイナリファイル: 10 → 12 bytes, 10aaaaa → 12aaaaa"
+            );
+        }
+
+        #[test]
+        fn returns_none_for_text_file() {
+            let mut git = MockGitProvider::new();
+            git.expect_blob_info()
+                .returning(|_, _, _| Ok(blob(10, false)));
+
+            let handler = DiffHandler::build(cmd(), &git, setup_data());
+            assert_eq!(handler.binary_change().unwrap(), None);
+        }
     }
 
     mod extract_old_lines {
@@ -183,15 +897,8 @@ mod tests {
         #[test]
         fn returns_vector_of_strings() {
             let mut git = MockGitProvider::new();
-            let scope = setup_scope_input();
             let data = setup_data();
-            let cmd = FFCommand {
-                scope,
-                old_range: "1-10".to_string(),
-                new_range: "11-20".to_string(),
-                source: SourceKind::Commit,
-                mode: DiffMode::Lines,
-            };
+            let cmd = cmd();
 
             git.expect_extract_lines()
                 .with(
@@ -212,15 +919,8 @@ mod tests {
         #[test]
         fn returns_error() {
             let mut git = MockGitProvider::new();
-            let scope = setup_scope_input();
             let data = setup_data();
-            let cmd = FFCommand {
-                scope,
-                old_range: "1-10".to_string(),
-                new_range: "11-20".to_string(),
-                source: SourceKind::Commit,
-                mode: DiffMode::Lines,
-            };
+            let cmd = cmd();
 
             git.expect_extract_lines()
                 .with(
@@ -239,21 +939,94 @@ mod tests {
         }
     }
 
+    mod merge_base {
+        use super::*;
+
+        #[test]
+        fn old_lines_come_from_merge_base() {
+            let mut git = MockGitProvider::new();
+            git.expect_merge_base()
+                .with(eq("main"), eq("feature"))
+                .returning(|_, _| Ok("abc123".to_string()));
+            git.expect_extract_lines()
+                .with(
+                    eq("abc123"),
+                    eq("old_file.txt"),
+                    eq(1),
+                    eq(10),
+                    eq(Some(SourceKind::Commit)),
+                )
+                .returning(|_, _, _, _, _| Ok(vec!["line1".to_string()]));
+
+            let cmd = FFCommand {
+                merge_base: true,
+                ..cmd()
+            };
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+            assert_eq!(
+                handler.extract_old_lines().unwrap().into_lines(),
+                vec!["line1"]
+            );
+        }
+
+        #[test]
+        fn merge_base_is_resolved_once() {
+            let mut git = MockGitProvider::new();
+            git.expect_merge_base()
+                .times(1)
+                .returning(|_, _| Ok("abc123".to_string()));
+            let cmd = FFCommand {
+                merge_base: true,
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+
+            assert_eq!(handler.old_revision().unwrap(), "abc123");
+            assert_eq!(handler.old_revision().unwrap(), "abc123");
+            assert_eq!(handler.revision_range().unwrap(), "main...feature");
+        }
+
+        #[test]
+        fn three_way_keeps_base_tip_and_treats_new_file_as_empty() {
+            let mut git = MockGitProvider::new();
+            git.expect_merge_base()
+                .returning(|_, _| Ok("abc123".to_string()));
+            git.expect_extract_lines()
+                .with(
+                    eq("abc123"),
+                    eq("old_file.txt"),
+                    eq(1),
+                    eq(10),
+                    eq(Some(SourceKind::Commit)),
+                )
+                .returning(|_, _, _, _, _| Err(GitError::FileNotFound));
+
+            let cmd = FFCommand {
+                layout: Layout::ThreeWay,
+                merge_base: true,
+                ..cmd()
+            };
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+            assert_eq!(handler.old_revision().unwrap(), "main");
+            assert!(
+                handler
+                    .extract_merge_base_lines()
+                    .unwrap()
+                    .into_lines()
+                    .is_empty()
+            );
+        }
+    }
+
     mod extract_new_lines {
         use super::*;
 
         #[test]
         fn returns_vector_of_strings() {
             let mut git = MockGitProvider::new();
-            let scope = setup_scope_input();
             let data = setup_data();
-            let cmd = FFCommand {
-                scope,
-                old_range: "1-10".to_string(),
-                new_range: "11-20".to_string(),
-                source: SourceKind::Commit,
-                mode: DiffMode::Lines,
-            };
+            let cmd = cmd();
 
             git.expect_extract_lines()
                 .with(
@@ -274,15 +1047,8 @@ mod tests {
         #[test]
         fn returns_error() {
             let mut git = MockGitProvider::new();
-            let scope = setup_scope_input();
             let data = setup_data();
-            let cmd = FFCommand {
-                scope,
-                old_range: "1-10".to_string(),
-                new_range: "11-20".to_string(),
-                source: SourceKind::Commit,
-                mode: DiffMode::Lines,
-            };
+            let cmd = cmd();
 
             git.expect_extract_lines()
                 .with(
@@ -301,6 +1067,410 @@ mod tests {
         }
     }
 
+    mod revision_summary {
+        use super::*;
+        use crate::git::CommitInfo;
+
+        fn commit(revision: &str) -> Result<CommitInfo, GitError> {
+            Ok(CommitInfo {
+                sha: format!("{:a>40}", revision.len()),
+                subject: format!("{} の件名", revision),
+            })
+        }
+
+        #[test]
+        fn shows_resolved_commits() {
+            let mut git = MockGitProvider::new();
+            git.expect_resolve_revision().returning(commit);
+            let cmd = cmd();
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+
+            assert_eq!(
+                handler.revision_summary().unwrap(),
+                "main (aaaaaaa main の件名) → feature (aaaaaaa feature の件名)"
+            );
+        }
+
+        #[test]
+        fn shows_merge_base_and_worktree() {
+            let mut git = MockGitProvider::new();
+            git.expect_merge_base()
+                .returning(|_, _| Ok("abc123".to_string()));
+            git.expect_resolve_revision()
+                .with(eq("abc123"))
+                .returning(commit);
+            let cmd = FFCommand {
+                new_source: SourceKind::Worktree,
+                merge_base: true,
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+
+            assert_eq!(
+                handler.revision_summary().unwrap(),
+                "main とのマージベース (aaaaaaa abc123 の件名) → feature の作業ツリー"
+            );
+        }
+
+        #[test]
+        fn shows_old_side_worktree() {
+            let mut git = MockGitProvider::new();
+            git.expect_resolve_revision()
+                .with(eq("feature"))
+                .returning(commit);
+            let cmd = FFCommand {
+                old_source: SourceKind::Worktree,
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+
+            assert_eq!(
+                handler.revision_summary().unwrap(),
+                "main の作業ツリー → feature (aaaaaaa feature の件名)"
+            );
+        }
+    }
+
+    mod ranges {
+        use super::*;
+        use crate::utils::test_support::text_of;
+
+        #[test]
+        fn patterns_resolve_against_each_side() {
+            let mut git = MockGitProvider::new();
+            git.expect_extract_text()
+                .returning(|branch, _, _| match branch {
+                    "main" => text_of(&["fn handle() {", "}"]),
+                    _ => text_of(&["use x;", "", "fn handle() {", "    run();", "}"]),
+                });
+            let cmd = FFCommand {
+                old_range: "/fn handle/../^}/".to_string(),
+                new_range: Some("/fn handle/../^}/".to_string()),
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+            let (old, new) = handler.ranges().unwrap();
+
+            assert_eq!((old[0].start(), old[0].end()), (1, 2));
+            assert_eq!((new[0].start(), new[0].end()), (3, 5));
+        }
+
+        #[test]
+        fn line_numbers_do_not_read_files() {
+            let git = MockGitProvider::new();
+            let cmd = cmd();
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+            let (old, new) = handler.ranges().unwrap();
+
+            assert_eq!((old[0].start(), new[0].end()), (1, 20));
+        }
+
+        #[test]
+        fn open_ended_range_reads_file() {
+            let mut git = MockGitProvider::new();
+            git.expect_extract_text()
+                .returning(|_, _, _| text_of(&["a", "b", "c"]));
+            let cmd = FFCommand {
+                old_range: "2-".to_string(),
+                new_range: Some("-$".to_string()),
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+            let (old, new) = handler.ranges().unwrap();
+
+            assert_eq!((old[0].start(), old[0].end()), (2, 3));
+            assert_eq!((new[0].start(), new[0].end()), (1, 3));
+        }
+
+        #[test]
+        fn omitted_new_range_follows_old_lines() {
+            let mut git = MockGitProvider::new();
+            git.expect_extract_text()
+                .returning(|branch, _, _| match branch {
+                    "main" => text_of(&["use x;", "fn handle() {", "    run();", "}"]),
+                    _ => text_of(&["use x;", "use y;", "", "fn handle() {", "    run();", "}"]),
+                });
+            let cmd = FFCommand {
+                old_range: "2-4".to_string(),
+                new_range: None,
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+            let (_, new) = handler.ranges().unwrap();
+
+            assert_eq!((new[0].start(), new[0].end()), (4, 6));
+        }
+
+        #[test]
+        fn omitted_new_range_fails_when_lines_are_deleted() {
+            let mut git = MockGitProvider::new();
+            git.expect_extract_text()
+                .returning(|branch, _, _| match branch {
+                    "main" => text_of(&["a", "b", "c"]),
+                    _ => text_of(&["a", "c"]),
+                });
+            let cmd = FFCommand {
+                old_range: "2".to_string(),
+                new_range: None,
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+
+            assert!(handler.ranges().is_err());
+        }
+
+        #[test]
+        fn split_ranges_keep_file_line_numbers() {
+            let mut git = MockGitProvider::new();
+            git.expect_extract_lines()
+                .returning(|branch, _, start, end, _| {
+                    let lines = (start..=end)
+                        .map(|n| match (branch, n) {
+                            ("feature", 41) => "changed".to_string(),
+                            (_, n) => format!("line {}", n),
+                        })
+                        .collect();
+                    Ok(lines)
+                });
+            let cmd = FFCommand {
+                old_range: "1-2,40-42".to_string(),
+                new_range: Some("1-2,40-42".to_string()),
+                format: OutputFormat::Json,
+                context: Some(ContextSize::Lines(0)),
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+            let old = handler.extract_old_lines().unwrap();
+            let new = handler.extract_new_lines().unwrap();
+            assert_eq!(old.pieces.len(), 2);
+            assert_eq!(old.pieces[1].len(), 3);
+
+            let result = handler.generate_diff(old, new).unwrap();
+            let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+            assert_eq!(json["old"]["ranges"][1]["start"], 40);
+            let hunk = &json["hunks"][0];
+            assert_eq!(hunk["old_start"], 41);
+            let changed = hunk["lines"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|line| line["new"]["content"] == "changed")
+                .unwrap();
+            assert_eq!(changed["new"]["lineno"], 41);
+        }
+
+        #[test]
+        fn split_ranges_cannot_be_patched() {
+            let mut git = MockGitProvider::new();
+            git.expect_extract_lines()
+                .returning(|_, _, _, _, _| Ok(vec!["a".to_string()]));
+            let cmd = FFCommand {
+                old_range: "1,5".to_string(),
+                new_range: Some("1,5".to_string()),
+                format: OutputFormat::Patch,
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+            let old = handler.extract_old_lines().unwrap();
+            let new = handler.extract_new_lines().unwrap();
+
+            assert!(handler.generate_diff(old, new).is_err());
+        }
+
+        #[test]
+        fn split_ranges_are_compared_piece_by_piece() {
+            let mut git = MockGitProvider::new();
+            git.expect_extract_lines()
+                .returning(|branch, _, start, end, _| {
+                    // 旧側の 2 つ目の範囲の行が、新側では 1 つ目の範囲に移っている
+                    let lines = match (branch, start) {
+                        ("main", 1) => vec!["a"],
+                        ("main", _) => vec!["z", "b"],
+                        (_, 1) => vec!["a", "z"],
+                        _ => vec!["b"],
+                    };
+                    assert!(lines.len() <= end - start + 1);
+                    Ok(lines.into_iter().map(String::from).collect())
+                });
+            let cmd = FFCommand {
+                old_range: "1,10-11".to_string(),
+                new_range: Some("1-2,20".to_string()),
+                stat: true,
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+            let old = handler.extract_old_lines().unwrap();
+            let new = handler.extract_new_lines().unwrap();
+
+            assert_eq!(
+                handler.generate_diff(old, new).unwrap(),
+                "+1 -1 ~0 (一致率 67%)"
+            );
+        }
+
+        #[test]
+        fn split_ranges_need_same_count_on_both_sides() {
+            let mut git = MockGitProvider::new();
+            git.expect_extract_lines()
+                .returning(|_, _, _, _, _| Ok(vec!["a".to_string()]));
+            let cmd = FFCommand {
+                old_range: "1,5".to_string(),
+                new_range: Some("1-5".to_string()),
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+            let old = handler.extract_old_lines().unwrap();
+            let new = handler.extract_new_lines().unwrap();
+
+            let err = handler.generate_diff(old, new).unwrap_err();
+            assert!(err.to_string().contains("(2 個と 1 個)"));
+        }
+
+        #[test]
+        fn ambiguous_pattern_names_the_file() {
+            let mut git = MockGitProvider::new();
+            git.expect_extract_text()
+                .returning(|_, _, _| text_of(&["fn a() {}", "fn b() {}"]));
+            let cmd = FFCommand {
+                old_range: "1-2".to_string(),
+                new_range: Some("/fn/+1".to_string()),
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+            let err = handler.ranges().unwrap_err();
+
+            assert!(
+                err.to_string()
+                    .starts_with("new_file.txt で /fn/ に一致する行が複数あります")
+            );
+        }
+    }
+
+    mod find {
+        use super::*;
+        use crate::git::BlobInfo;
+        use crate::utils::test_support::text_of;
+
+        #[test]
+        fn find_picks_most_similar_changed_file() {
+            let mut git = MockGitProvider::new();
+            git.expect_blob_info().returning(|_, path, _| {
+                Ok(BlobInfo {
+                    oid: String::new(),
+                    size: 0,
+                    binary: path.ends_with(".png"),
+                })
+            });
+            git.expect_list_changed_files().returning(|_, _| {
+                Ok(vec![
+                    "new_file.txt".to_string(),
+                    "moved.rs".to_string(),
+                    "logo.png".to_string(),
+                ])
+            });
+            git.expect_extract_lines().returning(|_, _, _, _, _| {
+                Ok(vec![
+                    "fn total(items: &[Item]) -> u32 {".to_string(),
+                    "}".to_string(),
+                ])
+            });
+            git.expect_extract_text()
+                .returning(|_, path, _| match path {
+                    "moved.rs" => {
+                        text_of(&["use x;", "", "pub fn total(items: &[Item]) -> u64 {", "}"])
+                    }
+                    _ => text_of(&["fn count() {", "}"]),
+                });
+            let cmd = FFCommand {
+                old_range: "1-2".to_string(),
+                new_range: None,
+                find: Some(FindScope::Changed),
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+            let (path, range) = handler.find_region(&FindScope::Changed).unwrap();
+
+            assert_eq!(path, "moved.rs");
+            assert_eq!((range.start(), range.end()), (3, 4));
+        }
+
+        #[test]
+        fn find_skips_unreadable_files_and_searches_from_merge_base() {
+            let mut git = MockGitProvider::new();
+            git.expect_merge_base()
+                .returning(|_, _| Ok("abc123".to_string()));
+            git.expect_blob_info().returning(|_, _, _| {
+                Ok(BlobInfo {
+                    oid: String::new(),
+                    size: 0,
+                    binary: false,
+                })
+            });
+            git.expect_list_changed_files()
+                .with(eq("abc123"), eq("feature"))
+                .returning(|_, _| Ok(vec!["legacy.txt".to_string(), "moved.rs".to_string()]));
+            git.expect_extract_lines()
+                .returning(|_, _, _, _, _| Ok(vec!["fn total() {".to_string()]));
+            git.expect_extract_text()
+                .returning(|_, path, _| match path {
+                    "legacy.txt" => Err(GitError::InvalidEncoding("Shift_JIS".to_string())),
+                    _ => text_of(&["use x;", "fn total() {"]),
+                });
+            let cmd = FFCommand {
+                old_range: "1".to_string(),
+                new_range: None,
+                merge_base: true,
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+            let (path, range) = handler.find_region(&FindScope::Changed).unwrap();
+
+            assert_eq!(path, "moved.rs");
+            assert_eq!((range.start(), range.end()), (2, 2));
+        }
+
+        #[test]
+        fn find_fails_without_similar_lines() {
+            let mut git = MockGitProvider::new();
+            git.expect_blob_info().returning(|_, _, _| {
+                Ok(BlobInfo {
+                    oid: String::new(),
+                    size: 0,
+                    binary: false,
+                })
+            });
+            git.expect_extract_lines()
+                .returning(|_, _, _, _, _| Ok(vec!["alpha".to_string()]));
+            git.expect_extract_text()
+                .returning(|_, _, _| text_of(&["beta"]));
+            let cmd = FFCommand {
+                old_range: "1".to_string(),
+                new_range: None,
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, setup_data());
+
+            assert!(handler.find_region(&FindScope::File).is_err());
+        }
+    }
+
     mod generate_diff {
         use super::*;
 
@@ -310,17 +1480,137 @@ mod tests {
             let new_lines = vec!["line3".to_string(), "line4".to_string()];
             let data = setup_data();
             let git = MockGitProvider::new();
-            let cmd = FFCommand {
-                scope: setup_scope_input(),
-                old_range: "1-10".to_string(),
-                new_range: "11-20".to_string(),
-                source: SourceKind::Commit,
-                mode: DiffMode::Lines,
-            };
+            let cmd = cmd();
 
             let handler = DiffHandler::build(cmd, &git, data);
             let result = handler.generate_diff(old_lines, new_lines);
             assert!(result.is_ok());
         }
+
+        #[test]
+        fn shows_crlf_as_visible_mark_in_text() {
+            let git = MockGitProvider::new();
+            let handler = DiffHandler::build(cmd(), &git, setup_data());
+
+            let result = handler
+                .generate_diff(vec!["a\r".to_string()], vec!["a".to_string()])
+                .unwrap();
+
+            assert!(result.contains("a␍"));
+            assert!(!result.contains('\r'));
+        }
+
+        #[test]
+        fn returns_patch_with_range_offsets() {
+            let old_lines = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+            let new_lines = vec!["a".to_string(), "x".to_string(), "c".to_string()];
+            let data = setup_data();
+            let git = MockGitProvider::new();
+            let cmd = FFCommand {
+                old_range: "10-12".to_string(),
+                new_range: Some("20-22".to_string()),
+                format: OutputFormat::Patch,
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, data);
+            let result = handler.generate_diff(old_lines, new_lines).unwrap();
+            assert_eq!(
+                result,
+                "--- a/old_file.txt\n+++ b/new_file.txt\n@@ -10,3 +20,3 @@\n a\n-b\n+x\n c\n"
+            );
+        }
+
+        #[test]
+        fn returns_stats_instead_of_diff() {
+            let old_lines = vec!["a".to_string(), "b".to_string()];
+            let new_lines = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+            let data = setup_data();
+            let git = MockGitProvider::new();
+            let cmd = FFCommand {
+                old_range: "1-2".to_string(),
+                new_range: Some("1-3".to_string()),
+                stat: true,
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, data);
+            let result = handler.generate_diff(old_lines, new_lines).unwrap();
+            assert_eq!(result, "+1 -0 ~0 (一致率 80%)");
+        }
+
+        #[test]
+        fn returns_json_report_with_file_positions() {
+            let old_lines = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+            let new_lines = vec!["a".to_string(), "x".to_string(), "c".to_string()];
+            let data = setup_data();
+            let git = MockGitProvider::new();
+            let cmd = FFCommand {
+                old_range: "10-12".to_string(),
+                new_range: Some("20-22".to_string()),
+                format: OutputFormat::Json,
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, data);
+            let result = handler.generate_diff(old_lines, new_lines).unwrap();
+            let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+            assert_eq!(json["old"]["path"], "old_file.txt");
+            assert_eq!(json["new"]["ranges"][0]["start"], 20);
+            assert_eq!(json["mode"], "lines");
+            assert!(json["merge_base"].is_null());
+            assert_eq!(json["stats"]["added"], 1);
+            assert_eq!(json["stats"]["removed"], 1);
+            let hunk = &json["hunks"][0];
+            assert_eq!(hunk["old_start"], 10);
+            assert_eq!(hunk["new_start"], 20);
+        }
+
+        #[test]
+        fn returns_html_document_with_file_positions() {
+            let old_lines = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+            let new_lines = vec!["a".to_string(), "x".to_string(), "c".to_string()];
+            let data = setup_data();
+            let git = MockGitProvider::new();
+            let cmd = FFCommand {
+                old_range: "10-12".to_string(),
+                new_range: Some("20-22".to_string()),
+                format: OutputFormat::Html,
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, data);
+            let result = handler.generate_diff(old_lines, new_lines).unwrap();
+
+            assert!(result.starts_with("<!DOCTYPE html>"));
+            assert!(result.contains("<title>main..feature</title>"));
+            assert!(result.contains("old_file.txt → new_file.txt"));
+            assert!(result.contains("@@ -10,3 +20,3 @@"));
+        }
+
+        #[test]
+        fn returns_markdown_with_suggestion() {
+            let old_lines = vec!["a".to_string(), "b".to_string()];
+            let new_lines = vec!["a".to_string(), "x".to_string()];
+            let data = setup_data();
+            let git = MockGitProvider::new();
+            let cmd = FFCommand {
+                old_range: "10-11".to_string(),
+                new_range: Some("20-21".to_string()),
+                format: OutputFormat::Markdown,
+                suggestion: true,
+                ..cmd()
+            };
+
+            let handler = DiffHandler::build(cmd, &git, data);
+            let result = handler.generate_diff(old_lines, new_lines).unwrap();
+
+            assert!(result.starts_with(
+                "**`old_file.txt` L10-L11 → `new_file.txt` L20-L21** (`main..feature`)\n\n```diff\n"
+            ));
+            assert!(result.contains("@@ -10,2 +20,2 @@\n a\n-b\n+x\n```\n"));
+            assert!(result.ends_with("```suggestion\na\nx\n```\n"));
+        }
     }
 }
@@ -0,0 +1,22 @@
+use std::fmt;
+
+use clap::ValueEnum;
+
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+pub enum Layout {
+    Inline,
+    SideBySide,
+    /// マージベース・ベースブランチ・比較対象の 3 列
+    ThreeWay,
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Layout::Inline => "inline",
+            Layout::SideBySide => "side-by-side",
+            Layout::ThreeWay => "three-way",
+        };
+        write!(f, "{}", text)
+    }
+}
@@ -0,0 +1,128 @@
+use super::Range;
+use super::report::ReportTarget;
+
+/// PR のコメントにそのまま貼れる、見出しと ```diff ブロックの Markdown
+pub struct MarkdownReport<'a> {
+    /// `main..feature` のような比較したリビジョンの範囲
+    pub revisions: String,
+    pub old: ReportTarget<'a>,
+    pub new: ReportTarget<'a>,
+    /// unified diff 形式のパッチ。差分がなければ空文字列
+    pub patch: String,
+    /// GitHub の suggestion ブロックにする新しい側の行
+    pub suggestion: Option<Vec<String>>,
+}
+
+impl MarkdownReport<'_> {
+    pub fn render(&self) -> String {
+        let new_path = if self.old.path == self.new.path {
+            String::new()
+        } else {
+            format!("`{}` ", self.new.path)
+        };
+        let mut out = format!(
+            "**`{}` {} → {}{}** (`{}`)\n\n",
+            self.old.path,
+            lines_label(&self.old.ranges),
+            new_path,
+            lines_label(&self.new.ranges),
+            self.revisions
+        );
+
+        if self.patch.is_empty() {
+            out.push_str("差分はありません\n");
+            return out;
+        }
+        out.push_str(&fenced("diff", self.patch.trim_end_matches('\n')));
+
+        if let Some(lines) = &self.suggestion {
+            out.push('\n');
+            out.push_str(&fenced("suggestion", &lines.join("\n")));
+        }
+
+        out
+    }
+}
+
+/// `L10-L12, L40-L55` のような範囲の表記
+fn lines_label(ranges: &[Range]) -> String {
+    ranges
+        .iter()
+        .map(|range| format!("L{}-L{}", range.start(), range.end()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 本文に含まれるより長いバッククォートで囲み、コード中の ``` でブロックが閉じないようにする
+fn fenced(info: &str, body: &str) -> String {
+    let longest = body.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{fence}{info}\n{body}\n{fence}\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::ff::RangeSpec;
+
+    fn target<'a>(path: &'a str, spec: &str) -> ReportTarget<'a> {
+        let ranges = RangeSpec::parse(spec).unwrap().lines().unwrap();
+        ReportTarget { path, ranges }
+    }
+
+    #[test]
+    fn renders_header_and_diff_block() {
+        let report = MarkdownReport {
+            revisions: "main..feature".to_string(),
+            old: target("src/a.rs", "10-12"),
+            new: target("src/a.rs", "20-22"),
+            patch: "--- a/src/a.rs\n+++ b/src/a.rs\n@@ -10 +20 @@\n-a\n+b\n".to_string(),
+            suggestion: Some(vec!["b".to_string()]),
+        };
+
+        assert_eq!(
+            report.render(),
+            "**`src/a.rs` L10-L12 → L20-L22** (`main..feature`)\n\n\
+             ```diff\n--- a/src/a.rs\n+++ b/src/a.rs\n@@ -10 +20 @@\n-a\n+b\n```\n\n\
+             ```suggestion\nb\n```\n"
+        );
+    }
+
+    #[test]
+    fn lists_each_split_range() {
+        let report = MarkdownReport {
+            revisions: "main..feature".to_string(),
+            old: target("a.rs", "10-12,40-55"),
+            new: target("a.rs", "10-12,41-56"),
+            patch: String::new(),
+            suggestion: None,
+        };
+
+        assert!(
+            report
+                .render()
+                .starts_with("**`a.rs` L10-L12, L40-L55 → L10-L12, L41-L56**")
+        );
+    }
+
+    #[test]
+    fn fence_is_longer_than_backticks_in_body() {
+        assert_eq!(fenced("diff", "+```rust"), "````diff\n+```rust\n````\n");
+    }
+
+    #[test]
+    fn empty_patch_has_no_blocks() {
+        let report = MarkdownReport {
+            revisions: "main..feature".to_string(),
+            old: target("old.rs", "1-3"),
+            new: target("new.rs", "1-3"),
+            patch: String::new(),
+            suggestion: Some(vec![]),
+        };
+
+        assert_eq!(
+            report.render(),
+            "**`old.rs` L1-L3 → `new.rs` L1-L3** (`main..feature`)\n\n差分はありません\n"
+        );
+    }
+}
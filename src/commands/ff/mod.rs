@@ -1,16 +1,29 @@
+mod find_scope;
+mod format;
 mod handler;
+mod layout;
+mod markdown;
 mod mode;
 mod range;
+mod report;
 mod scope_input;
+mod selection;
 mod validated_config;
+mod whitespace_input;
 
 use crate::config::Manager;
+pub use find_scope::FindScope;
+pub use format::OutputFormat;
 use handler::DiffHandler;
+pub use layout::Layout;
 pub use mode::DiffMode;
-use range::Range;
+use range::{Range, RangeSpec};
 pub use scope_input::ScopeCommandInput;
+use selection::Selection;
+pub use whitespace_input::WhitespaceInput;
 
 use crate::commands::error::CommandError;
+use crate::diff::{ContextSize, DiffAlgorithm};
 use clap::Args;
 
 use crate::git::{GitProvider, core::SourceKind};
@@ -23,14 +36,72 @@ pub struct FFCommand {
     #[arg(value_name = "OLD_FILE_RANGE")]
     pub old_range: String,
 
+    /// 省略すると、ファイル全体の差分から旧側の範囲に対応する新側の範囲を求める
     #[arg(value_name = "NEW_FILE_RANGE")]
-    pub new_range: String,
+    pub new_range: Option<String>,
 
-    #[arg(short, long, value_enum, default_value_t = SourceKind::Commit)]
-    pub source: SourceKind,
+    /// 旧側の範囲の内容にもっとも似た箇所を探して比較する。
+    /// --find=changed なら、対象ブランチ側のファイルではなく変更されたすべてのファイルから探す
+    #[arg(
+        long,
+        value_enum,
+        value_name = "SCOPE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "file",
+        conflicts_with = "new_range"
+    )]
+    pub find: Option<FindScope>,
+
+    /// 古い側 (ベースブランチ) のファイルの取り出し元
+    #[arg(long, value_enum, default_value_t = SourceKind::Commit)]
+    pub old_source: SourceKind,
+
+    /// 新しい側 (比較対象のブランチ) のファイルの取り出し元。
+    /// worktree なら、コミットしていない作業ツリーの内容と比較する
+    #[arg(
+        short = 's',
+        long = "new-source",
+        visible_alias = "source",
+        value_enum,
+        default_value_t = SourceKind::Commit
+    )]
+    pub new_source: SourceKind,
 
     #[arg(short, long, value_enum, default_value_t = DiffMode::Slice)]
     pub mode: DiffMode,
+
+    #[arg(short, long, value_enum, default_value_t = DiffAlgorithm::Myers)]
+    pub algorithm: DiffAlgorithm,
+
+    #[command(flatten)]
+    pub whitespace: WhitespaceInput,
+
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    #[arg(short, long, value_enum, default_value_t = Layout::Inline)]
+    pub layout: Layout,
+
+    /// side-by-side で長い行を切り詰めずに折り返す
+    #[arg(long)]
+    pub wrap: bool,
+
+    /// 変更行の前後に表示する行数 (full でファイル全体)。未指定なら設定値を使う
+    #[arg(short = 'U', long, value_name = "LINES")]
+    pub context: Option<ContextSize>,
+
+    /// 差分の代わりに、追加・削除・置換された行数と一致率を表示する
+    #[arg(long)]
+    pub stat: bool,
+
+    /// ベースブランチの先端ではなく、分岐したコミット (マージベース) と比較する (git diff A...B)
+    #[arg(long)]
+    pub merge_base: bool,
+
+    /// --format markdown で、新しい側の行を GitHub の suggestion ブロックとしても出力する
+    #[arg(long)]
+    pub suggestion: bool,
 }
 
 pub fn handle(
@@ -8,6 +8,8 @@ pub enum DiffMode {
     Words,
     Lines,
     Chars,
+    /// lines と同じ表示を、文脈行数で区切ったハンクごとに出す
+    Hunks,
 }
 
 impl fmt::Display for DiffMode {
@@ -17,6 +19,7 @@ impl fmt::Display for DiffMode {
             DiffMode::Words => "words",
             DiffMode::Lines => "lines",
             DiffMode::Chars => "chars",
+            DiffMode::Hunks => "hunks",
         };
         write!(f, "{}", text)
     }
@@ -1,28 +1,17 @@
+use regex::Regex;
+use serde::Serialize;
+
 use crate::commands::error::CommandError;
 
+/// 取り出す行の範囲 (1 始まり、両端を含む)
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
 pub struct Range {
     start: usize,
     end: usize,
 }
 
 impl Range {
-    pub fn parse(range: &str) -> Result<Self, CommandError> {
-        let parts: Vec<&str> = range.split('-').collect();
-        if parts.len() != 2 {
-            return Err(CommandError::ArgParse(format!(
-                "範囲 '{}' が正しくありません。例: 1-10",
-                range
-            )));
-        }
-
-        let start = parts[0].parse::<usize>().map_err(|_| {
-            CommandError::ArgParse(format!("開始値 '{}' が数値ではありません。", parts[0]))
-        })?;
-
-        let end = parts[1].parse::<usize>().map_err(|_| {
-            CommandError::ArgParse(format!("終了値 '{}' が数値ではありません。", parts[1]))
-        })?;
-
+    pub(super) fn new(start: usize, end: usize) -> Result<Self, CommandError> {
         if start > end {
             return Err(CommandError::InvalidInput(format!(
                 "開始値 '{}' は終了値 '{}' より大きくはできません。",
@@ -41,32 +30,495 @@ impl Range {
     }
 }
 
+/// 範囲の端。行番号か `$` (最終行)、ファイルの内容から探す `/正規表現/`
+#[derive(Debug)]
+enum Bound {
+    Line(usize),
+    Last,
+    Pattern(Regex),
+}
+
+/// 範囲の終わり方
+#[derive(Debug)]
+enum End {
+    /// 開始行だけ
+    Single,
+    /// `-` または `..` の後ろ。省略するとファイルの末尾まで
+    To(Option<Bound>),
+    /// `+K` で開始行から K 行 (git log -L と同じく開始行を含む)
+    Count(usize),
+}
+
+/// カンマで区切った範囲の 1 つ
+#[derive(Debug)]
+struct Piece {
+    start: Bound,
+    end: End,
+}
+
+/// ff の範囲指定。`10-20` のような行番号のほか、次の指定ができる。
+/// 内容から探す指定は、ブランチごとのファイルの内容で行番号に直す
+///
+/// - `10` / `10-` / `-20` / `10+5` / `$` : 1 行だけ、末尾まで、先頭から、10 行目から 5 行 (10-14)、最終行
+/// - `/fn handle/..` / `/^impl Foo/+20` / `/^fn a/../^}/` : 正規表現に一致する行から
+/// - `1-10,40-55` : 離れた複数の範囲
+#[derive(Debug)]
+pub struct RangeSpec {
+    pieces: Vec<Piece>,
+}
+
+impl RangeSpec {
+    pub fn parse(spec: &str) -> Result<Self, CommandError> {
+        let mut pieces = Vec::new();
+        let mut rest = spec;
+        loop {
+            let (piece, next) = parse_piece(rest).map_err(|e| match e {
+                ParseError::Invalid => invalid_spec(spec),
+                ParseError::Command(e) => e,
+            })?;
+            pieces.push(piece);
+            match next.strip_prefix(',') {
+                Some(next) => rest = next,
+                None if next.is_empty() => break,
+                None => return Err(invalid_spec(spec)),
+            }
+        }
+
+        // 行番号だけの範囲は、ファイルを読む前に大小と行数の上限を確かめる
+        for piece in &pieces {
+            match (&piece.start, &piece.end) {
+                (Bound::Line(start), End::To(Some(Bound::Line(end)))) => {
+                    Range::new(*start, *end)?;
+                }
+                (Bound::Line(start), End::Count(count)) => {
+                    count_end(*start, *count)?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self { pieces })
+    }
+
+    /// 内容を見なくても行番号が決まる指定なら、その範囲を返す
+    pub fn lines(&self) -> Option<Vec<Range>> {
+        self.pieces
+            .iter()
+            .map(|piece| {
+                let Bound::Line(start) = piece.start else {
+                    return None;
+                };
+                let end = match piece.end {
+                    End::Single => start,
+                    End::Count(count) => count_end(start, count).ok()?,
+                    End::To(Some(Bound::Line(end))) => end,
+                    End::To(_) => return None,
+                };
+                Range::new(start, end).ok()
+            })
+            .collect()
+    }
+
+    /// ファイルの内容から行番号の範囲を求める。`file_path` はエラーメッセージに使う
+    pub fn resolve(&self, lines: &[String], file_path: &str) -> Result<Vec<Range>, CommandError> {
+        // CRLF のファイルでも `$` が行末に一致するよう、パターンは `\r` を除いた行と照合する
+        let lines: Vec<&str> = lines
+            .iter()
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+            .collect();
+        self.pieces
+            .iter()
+            .map(|piece| resolve_piece(piece, &lines, file_path))
+            .collect()
+    }
+}
+
+fn resolve_piece(piece: &Piece, lines: &[&str], file_path: &str) -> Result<Range, CommandError> {
+    let start = match &piece.start {
+        Bound::Line(line) => *line,
+        Bound::Last => lines.len(),
+        Bound::Pattern(pattern) => find_unique(pattern, lines, file_path)?,
+    };
+    let end = match &piece.end {
+        End::Single => start,
+        End::Count(count) => count_end(start, *count)?,
+        End::To(None) | End::To(Some(Bound::Last)) => lines.len().max(start),
+        End::To(Some(Bound::Line(line))) => *line,
+        // 終了側は、開始行以降で最初に一致した行にする (ブロックの閉じ括弧などは何度も現れるため)
+        End::To(Some(Bound::Pattern(pattern))) => lines
+            .iter()
+            .enumerate()
+            .skip(start.saturating_sub(1))
+            .find(|(_, line)| pattern.is_match(line))
+            .map(|(i, _)| i + 1)
+            .ok_or_else(|| {
+                CommandError::InvalidInput(format!(
+                    "{} の {} 行目以降に /{}/ に一致する行がありません",
+                    file_path, start, pattern
+                ))
+            })?,
+    };
+    Range::new(start, end)
+}
+
+/// `start+count` の終了行。`count` 行目が表せないほど大きければエラー
+fn count_end(start: usize, count: usize) -> Result<usize, CommandError> {
+    start.checked_add(count - 1).ok_or_else(|| {
+        CommandError::InvalidInput(format!("範囲 '{}+{}' の行数が大きすぎます", start, count))
+    })
+}
+
+enum ParseError {
+    /// 書式の誤り。指定全体を含めたメッセージにする
+    Invalid,
+    Command(CommandError),
+}
+
+/// 範囲を 1 つ読み、残りの文字列と一緒に返す
+fn parse_piece(spec: &str) -> Result<(Piece, &str), ParseError> {
+    let (start, rest) = match parse_bound(spec)? {
+        Some(bound) => bound,
+        // `-20` や `..20` のように開始を省略した場合は先頭から
+        None if spec.starts_with(['-', '.']) => (Bound::Line(1), spec),
+        None => return Err(ParseError::Invalid),
+    };
+
+    if let Some(rest) = rest.strip_prefix("..").or_else(|| rest.strip_prefix('-')) {
+        return match parse_bound(rest)? {
+            Some((end, rest)) => Ok((
+                Piece {
+                    start,
+                    end: End::To(Some(end)),
+                },
+                rest,
+            )),
+            None => Ok((
+                Piece {
+                    start,
+                    end: End::To(None),
+                },
+                rest,
+            )),
+        };
+    }
+    if let Some(rest) = rest.strip_prefix('+') {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let count = rest[..digits].parse().map_err(|_| ParseError::Invalid)?;
+        if count == 0 {
+            return Err(ParseError::Command(CommandError::InvalidInput(
+                "'+0' は指定できません。N+K は N 行目から K 行を取り出すため、K は 1 以上にしてください"
+                    .to_string(),
+            )));
+        }
+        return Ok((
+            Piece {
+                start,
+                end: End::Count(count),
+            },
+            &rest[digits..],
+        ));
+    }
+    Ok((
+        Piece {
+            start,
+            end: End::Single,
+        },
+        rest,
+    ))
+}
+
+/// 先頭の行番号・`$`・`/正規表現/` を読み、残りの文字列と一緒に返す。`\/` は `/` として扱う
+fn parse_bound(spec: &str) -> Result<Option<(Bound, &str)>, ParseError> {
+    if let Some(rest) = spec.strip_prefix('$') {
+        return Ok(Some((Bound::Last, rest)));
+    }
+    let Some(body) = spec.strip_prefix('/') else {
+        let digits = spec
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(spec.len());
+        if digits == 0 {
+            return Ok(None);
+        }
+        let line = spec[..digits].parse().map_err(|_| ParseError::Invalid)?;
+        return Ok(Some((Bound::Line(line), &spec[digits..])));
+    };
+
+    let mut pattern = String::new();
+    let mut chars = body.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '/' => {
+                let regex = Regex::new(&pattern).map_err(|e| {
+                    ParseError::Command(CommandError::ArgParse(format!(
+                        "正規表現 /{}/ が不正です: {}",
+                        pattern, e
+                    )))
+                })?;
+                return Ok(Some((Bound::Pattern(regex), &body[i + 1..])));
+            }
+            '\\' => match chars.next() {
+                Some((_, '/')) => pattern.push('/'),
+                Some((_, next)) => {
+                    pattern.push('\\');
+                    pattern.push(next);
+                }
+                None => pattern.push('\\'),
+            },
+            c => pattern.push(c),
+        }
+    }
+    Err(ParseError::Command(CommandError::ArgParse(format!(
+        "正規表現 '{}' が '/' で閉じられていません",
+        spec
+    ))))
+}
+
+/// 開始行のパターンは、どの箇所を指すか曖昧にならないよう 1 行だけに一致する必要がある
+fn find_unique(pattern: &Regex, lines: &[&str], file_path: &str) -> Result<usize, CommandError> {
+    let matched: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| pattern.is_match(line))
+        .map(|(i, _)| i + 1)
+        .collect();
+
+    match matched.as_slice() {
+        [line] => Ok(*line),
+        [] => Err(CommandError::InvalidInput(format!(
+            "{} に /{}/ に一致する行がありません",
+            file_path, pattern
+        ))),
+        lines => Err(CommandError::InvalidInput(format!(
+            "{} で /{}/ に一致する行が複数あります ({} 行目)。パターンを絞り込んでください",
+            file_path,
+            pattern,
+            lines
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+    }
+}
+
+fn invalid_spec(spec: &str) -> CommandError {
+    CommandError::ArgParse(format!(
+        "範囲 '{}' が正しくありません。例: 1-10, 5, 10-, 10+5, 1-10,40-55, /fn handle/.., /^impl Foo/+20",
+        spec
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_parse_valid_range() {
-        let range = Range::parse("1-10").unwrap();
-        assert_eq!(range.start(), 1);
-        assert_eq!(range.end(), 10);
+        let ranges = RangeSpec::parse("1-10").unwrap().lines().unwrap();
+        assert_eq!(ranges[0].start(), 1);
+        assert_eq!(ranges[0].end(), 10);
     }
 
     #[test]
     fn test_parse_invalid_range() {
-        let result = Range::parse("10-1");
+        let result = RangeSpec::parse("10-1");
         assert!(result.is_err());
     }
 
     #[test]
     fn test_parse_non_numeric_range() {
-        let result = Range::parse("a-b");
+        let result = RangeSpec::parse("a-b");
         assert!(result.is_err());
     }
 
     #[test]
     fn test_parse_invalid_range_format() {
-        let result = Range::parse("1-1a");
+        let result = RangeSpec::parse("1-1a");
         assert!(result.is_err());
     }
+
+    mod spec {
+        use super::*;
+        use crate::utils::test_support::lines_of;
+
+        fn source() -> Vec<String> {
+            lines_of(&[
+                "use std::fmt;",
+                "",
+                "fn handle() {",
+                "    run();",
+                "}",
+                "",
+                "impl Foo {",
+                "    fn run() {}",
+                "}",
+            ])
+        }
+
+        fn resolve(spec: &str) -> Result<Vec<Range>, CommandError> {
+            RangeSpec::parse(spec)?.resolve(&source(), "src/lib.rs")
+        }
+
+        fn range(start: usize, end: usize) -> Vec<Range> {
+            vec![Range { start, end }]
+        }
+
+        #[test]
+        fn patterns_match_crlf_lines_up_to_cr() {
+            let crlf = lines_of(&["fn handle() {\r", "    run();\r", "}\r"]);
+            let spec = RangeSpec::parse("/^fn handle\\(\\) \\{$/../^}$/").unwrap();
+
+            assert_eq!(spec.resolve(&crlf, "src/lib.rs").unwrap(), range(1, 3));
+        }
+
+        #[test]
+        fn line_numbers_do_not_need_content() {
+            let spec = RangeSpec::parse("3-5").unwrap();
+            assert_eq!(spec.lines(), Some(range(3, 5)));
+            assert!(RangeSpec::parse("/fn/..").unwrap().lines().is_none());
+            assert!(RangeSpec::parse("3-").unwrap().lines().is_none());
+        }
+
+        #[test]
+        fn single_line_and_relative_count() {
+            assert_eq!(RangeSpec::parse("4").unwrap().lines(), Some(range(4, 4)));
+            assert_eq!(RangeSpec::parse("4+2").unwrap().lines(), Some(range(4, 5)));
+            assert_eq!(
+                RangeSpec::parse("10+5").unwrap().lines(),
+                Some(range(10, 14))
+            );
+            assert_eq!(
+                RangeSpec::parse("10+1").unwrap().lines(),
+                Some(range(10, 10))
+            );
+        }
+
+        #[test]
+        fn rejects_zero_count() {
+            assert!(matches!(
+                RangeSpec::parse("10+0"),
+                Err(CommandError::InvalidInput(message)) if message.starts_with("'+0'")
+            ));
+            assert!(RangeSpec::parse("/fn/+0").is_err());
+        }
+
+        #[test]
+        fn count_overflow_is_an_error() {
+            let err = RangeSpec::parse("2+18446744073709551615").unwrap_err();
+            assert_eq!(
+                err,
+                CommandError::InvalidInput(
+                    "範囲 '2+18446744073709551615' の行数が大きすぎます".to_string()
+                )
+            );
+            assert!(RangeSpec::parse("1+18446744073709551615").is_ok());
+
+            let spec = RangeSpec::parse("/^impl Foo/+18446744073709551615").unwrap();
+            assert!(spec.resolve(&source(), "src/lib.rs").is_err());
+        }
+
+        #[test]
+        fn open_ended_ranges() {
+            assert_eq!(resolve("7-").unwrap(), range(7, 9));
+            assert_eq!(resolve("-3").unwrap(), range(1, 3));
+            assert_eq!(resolve("5-$").unwrap(), range(5, 9));
+            assert_eq!(resolve("$").unwrap(), range(9, 9));
+        }
+
+        #[test]
+        fn comma_separated_ranges() {
+            let spec = RangeSpec::parse("1-2,/^impl/../^}/,9").unwrap();
+            assert!(spec.lines().is_none());
+            assert_eq!(
+                spec.resolve(&source(), "src/lib.rs").unwrap(),
+                vec![
+                    Range { start: 1, end: 2 },
+                    Range { start: 7, end: 9 },
+                    Range { start: 9, end: 9 }
+                ]
+            );
+            assert_eq!(
+                RangeSpec::parse("1-10,40-55").unwrap().lines(),
+                Some(vec![
+                    Range { start: 1, end: 10 },
+                    Range { start: 40, end: 55 }
+                ])
+            );
+        }
+
+        #[test]
+        fn comma_inside_pattern_is_not_a_separator() {
+            let lines = lines_of(&["fn a(x, y) {", "}"]);
+            let spec = RangeSpec::parse("/a\\(x, y\\)/+2").unwrap();
+            assert_eq!(spec.resolve(&lines, "a.rs").unwrap(), range(1, 2));
+        }
+
+        #[test]
+        fn pattern_to_end_of_file() {
+            assert_eq!(resolve("/fn handle/..").unwrap(), range(3, 9));
+        }
+
+        #[test]
+        fn pattern_with_count() {
+            assert_eq!(resolve("/^impl Foo/+3").unwrap(), range(7, 9));
+            assert_eq!(resolve("/^impl Foo/+1").unwrap(), range(7, 7));
+        }
+
+        #[test]
+        fn end_pattern_is_first_match_after_start() {
+            assert_eq!(resolve("/^impl/../^}/").unwrap(), range(7, 9));
+            assert_eq!(resolve("/^fn handle/../^}/").unwrap(), range(3, 5));
+        }
+
+        #[test]
+        fn escaped_slash_is_part_of_pattern() {
+            let lines = lines_of(&["// a/b", "x"]);
+            let spec = RangeSpec::parse(r"/a\/b/+2").unwrap();
+            assert_eq!(spec.resolve(&lines, "a.rs").unwrap(), range(1, 2));
+        }
+
+        #[test]
+        fn start_pattern_must_match_exactly_once() {
+            let err = resolve("/nothing/..").unwrap_err();
+            assert_eq!(
+                err,
+                CommandError::InvalidInput(
+                    "src/lib.rs に /nothing/ に一致する行がありません".to_string()
+                )
+            );
+
+            let err = resolve("/fn /..").unwrap_err();
+            assert_eq!(
+                err,
+                CommandError::InvalidInput(
+                    "src/lib.rs で /fn / に一致する行が複数あります (3, 8 行目)。パターンを絞り込んでください"
+                        .to_string()
+                )
+            );
+        }
+
+        #[test]
+        fn missing_end_pattern_is_an_error() {
+            assert!(resolve("/^impl/../^enum/").is_err());
+        }
+
+        #[test]
+        fn rejects_malformed_specs() {
+            for spec in [
+                "",
+                "/fn",
+                "/fn/+x",
+                "/fn/../x/y",
+                "/(/..",
+                "1-2-3",
+                "1-10,",
+                ",5",
+                "x",
+            ] {
+                assert!(RangeSpec::parse(spec).is_err(), "{spec}");
+            }
+        }
+    }
 }
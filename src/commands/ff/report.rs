@@ -0,0 +1,37 @@
+use serde::Serialize;
+
+use super::Range;
+use crate::commands::error::CommandError;
+use crate::commands::files::BinaryChange;
+use crate::diff::{DiffStats, Hunk};
+
+/// `--format json` で出力する差分と、その取り出し元の情報
+#[derive(Serialize)]
+pub struct DiffReport<'a> {
+    pub base_branch: &'a str,
+    pub target_branch: &'a str,
+    /// --merge-base の場合に、旧側として使ったマージベースの SHA
+    pub merge_base: Option<String>,
+    pub old: ReportTarget<'a>,
+    pub new: ReportTarget<'a>,
+    pub mode: String,
+    pub stats: Option<DiffStats>,
+    /// 行番号はファイル全体での位置。--stat の場合は出力しない
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hunks: Option<Vec<Hunk>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub binary: Option<BinaryChange>,
+}
+
+/// 比較したファイルと範囲 (1 始まり、両端を含む)。複数の範囲を指定した場合は指定した順に並ぶ
+#[derive(Serialize)]
+pub struct ReportTarget<'a> {
+    pub path: &'a str,
+    pub ranges: Vec<Range>,
+}
+
+impl DiffReport<'_> {
+    pub fn to_json(&self) -> Result<String, CommandError> {
+        serde_json::to_string_pretty(self).map_err(|e| CommandError::InternalError(e.to_string()))
+    }
+}
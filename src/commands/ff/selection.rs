@@ -0,0 +1,56 @@
+/// 複数の範囲を並べて表示するとき、範囲の見出しの先頭に付ける記号。表示にだけ使い、比較する行には含めない
+pub const SEPARATOR: &str = "⋯";
+
+/// 範囲ごとに取り出した行。離れた複数の範囲は 1 つにつながず、範囲ごとに別々に比較する
+#[derive(Debug, PartialEq)]
+pub struct Selection {
+    pub pieces: Vec<Vec<String>>,
+}
+
+impl Selection {
+    pub fn new(pieces: Vec<Vec<String>>) -> Self {
+        Self { pieces }
+    }
+
+    /// 離れた複数の範囲から取り出したものか
+    pub fn is_split(&self) -> bool {
+        self.pieces.len() > 1
+    }
+
+    /// すべての範囲の行を順に並べる
+    pub fn into_lines(self) -> Vec<String> {
+        self.pieces.into_iter().flatten().collect()
+    }
+}
+
+/// 1 つの範囲から取り出した行
+impl From<Vec<String>> for Selection {
+    fn from(lines: Vec<String>) -> Self {
+        Self {
+            pieces: vec![lines],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_support::lines_of;
+
+    #[test]
+    fn keeps_pieces_apart() {
+        let selection = Selection::new(vec![lines_of(&["b", "c"]), lines_of(&["j", "k"])]);
+
+        assert!(selection.is_split());
+        assert_eq!(selection.pieces[1], lines_of(&["j", "k"]));
+        assert_eq!(selection.into_lines(), lines_of(&["b", "c", "j", "k"]));
+    }
+
+    #[test]
+    fn single_piece_is_not_split() {
+        let selection = Selection::from(lines_of(&["a", "b"]));
+
+        assert!(!selection.is_split());
+        assert_eq!(selection.pieces.len(), 1);
+    }
+}
@@ -0,0 +1,65 @@
+use clap::Args;
+
+use crate::diff::WhitespaceMode;
+
+#[derive(Debug, Args, PartialEq, Default)]
+pub struct WhitespaceInput {
+    /// 空白をすべて無視して比較する
+    #[arg(short = 'w', long)]
+    pub ignore_all_space: bool,
+
+    /// 空白の量の違いを無視して比較する
+    #[arg(long)]
+    pub ignore_space_change: bool,
+
+    /// 行末の空白を無視して比較する
+    #[arg(long)]
+    pub ignore_space_at_eol: bool,
+
+    /// 空行だけの追加・削除を無視する
+    #[arg(long)]
+    pub ignore_blank_lines: bool,
+
+    /// 行末の CR を無視し、CRLF と LF の改行の違いを差分に出さない
+    #[arg(long)]
+    pub ignore_cr_at_eol: bool,
+
+    /// 両側の行がこの正規表現に一致すれば、同じ行として扱う (git diff -I)。設定の ignore_patterns に追加される
+    #[arg(short = 'I', long, value_name = "REGEX")]
+    pub ignore_matching: Vec<String>,
+}
+
+impl WhitespaceInput {
+    /// git と同じく、複数指定された場合は無視する範囲の広いものを優先する
+    pub fn mode(&self) -> WhitespaceMode {
+        if self.ignore_all_space {
+            WhitespaceMode::IgnoreAll
+        } else if self.ignore_space_change {
+            WhitespaceMode::IgnoreChange
+        } else if self.ignore_space_at_eol {
+            WhitespaceMode::IgnoreTrailing
+        } else {
+            WhitespaceMode::None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_to_none() {
+        assert_eq!(WhitespaceInput::default().mode(), WhitespaceMode::None);
+    }
+
+    #[test]
+    fn ignore_all_space_wins() {
+        let input = WhitespaceInput {
+            ignore_all_space: true,
+            ignore_space_change: true,
+            ..Default::default()
+        };
+        assert_eq!(input.mode(), WhitespaceMode::IgnoreAll);
+    }
+}
@@ -0,0 +1,313 @@
+use std::fmt;
+
+use clap::Args;
+use serde::Serialize;
+
+use crate::commands::error::CommandError;
+use crate::config::Manager;
+use crate::diff::{Diff, DiffStats};
+use crate::git::{BlobInfo, GitError, GitProvider, TextInfo, core::SourceKind};
+
+#[derive(Args)]
+pub struct FilesCommand {
+    /// ファイルごとに追加・削除・置換された行数と一致率を表示する
+    #[arg(long)]
+    pub stat: bool,
+}
+
+/// ベースブランチとの間で変更されたファイルと、その差分の大きさ。
+/// バイナリファイルは行数を数えられないため、`stats` と `text` の代わりに `binary` を持つ
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ChangedFile {
+    pub path: String,
+    pub stats: Option<DiffStats>,
+    pub text: Option<TextChange>,
+    pub binary: Option<BinaryChange>,
+}
+
+/// テキストファイルの文字コードと改行コード。片側にしかないファイルは、もう片側が `None`
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TextChange {
+    pub old: Option<TextInfo>,
+    pub new: Option<TextInfo>,
+}
+
+/// バイナリファイルの変更。片側にしかないファイルは、もう片側が `None`
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct BinaryChange {
+    pub old: Option<BlobInfo>,
+    pub new: Option<BlobInfo>,
+}
+
+impl fmt::Display for BinaryChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let size = |info: &Option<BlobInfo>| {
+            info.as_ref()
+                .map_or("-".to_string(), |info| info.size.to_string())
+        };
+        let oid = |info: &Option<BlobInfo>| {
+            info.as_ref()
+                .map_or("-".to_string(), |info| info.oid.chars().take(7).collect())
+        };
+        write!(
+            f,
+            "バイナリファイル: {} → {} bytes, {} → {}",
+            size(&self.old),
+            size(&self.new),
+            oid(&self.old),
+            oid(&self.new)
+        )
+    }
+}
+
+pub enum FileDiff {
+    Text(Diff, TextChange),
+    Binary(BinaryChange),
+}
+
+/// どちらかの側がバイナリなら、行を取り出さずに大きさと OID だけを返す
+pub fn binary_change(
+    git: &dyn GitProvider,
+    (base_branch, old_path): (&str, &str),
+    (target_branch, new_path): (&str, &str),
+    (old_source, new_source): (SourceKind, SourceKind),
+) -> Result<Option<BinaryChange>, GitError> {
+    let (old, new) = either_side(
+        git.blob_info(base_branch, old_path, Some(old_source)),
+        git.blob_info(target_branch, new_path, Some(new_source)),
+    )?;
+
+    let is_binary = [&old, &new].into_iter().flatten().any(|info| info.binary);
+    Ok(is_binary.then_some(BinaryChange { old, new }))
+}
+
+/// ファイルがない側だけを `None` にする。読み出しや文字コードの失敗はそのままエラーにし、
+/// 両側ともファイルがなければ `FileNotFound` を返す
+fn either_side<T>(
+    old: Result<T, GitError>,
+    new: Result<T, GitError>,
+) -> Result<(Option<T>, Option<T>), GitError> {
+    let missing_as_none = |result: Result<T, GitError>| match result {
+        Ok(value) => Ok(Some(value)),
+        Err(GitError::FileNotFound) => Ok(None),
+        Err(e) => Err(e),
+    };
+    match (missing_as_none(old)?, missing_as_none(new)?) {
+        (None, None) => Err(GitError::FileNotFound),
+        sides => Ok(sides),
+    }
+}
+
+/// ファイル全体を比較する。片側にしかないファイルは、もう片側を空として扱う
+pub fn load_file_diff(
+    git: &dyn GitProvider,
+    base_branch: &str,
+    target_branch: &str,
+    file_path: &str,
+) -> Result<FileDiff, GitError> {
+    load_paths_diff(git, (base_branch, file_path), (target_branch, file_path))
+}
+
+/// 旧側と新側でパスが異なるファイル同士 (スコープなど) を全体で比較する
+pub fn load_paths_diff(
+    git: &dyn GitProvider,
+    (base_branch, old_path): (&str, &str),
+    (target_branch, new_path): (&str, &str),
+) -> Result<FileDiff, GitError> {
+    if let Some(change) = binary_change(
+        git,
+        (base_branch, old_path),
+        (target_branch, new_path),
+        (SourceKind::Commit, SourceKind::Commit),
+    )? {
+        return Ok(FileDiff::Binary(change));
+    }
+
+    let (old, new) = either_side(
+        git.extract_text(base_branch, old_path, Some(SourceKind::Commit)),
+        git.extract_text(target_branch, new_path, Some(SourceKind::Commit)),
+    )?;
+
+    let (old_lines, old_info) = old.map_or((vec![], None), |text| (text.lines, Some(text.info)));
+    let (new_lines, new_info) = new.map_or((vec![], None), |text| (text.lines, Some(text.info)));
+    let change = TextChange {
+        old: old_info,
+        new: new_info,
+    };
+    Ok(FileDiff::Text(Diff::new(old_lines, new_lines), change))
+}
+
+pub fn list_changed_files_with_stats(
+    git: &dyn GitProvider,
+    base_branch: &str,
+    target_branch: &str,
+) -> Result<Vec<ChangedFile>, GitError> {
+    git.list_changed_files(base_branch, target_branch)?
+        .into_iter()
+        .map(|path| {
+            let file = match load_file_diff(git, base_branch, target_branch, &path)? {
+                FileDiff::Text(diff, change) => ChangedFile {
+                    path,
+                    stats: Some(diff.stats()),
+                    text: Some(change),
+                    binary: None,
+                },
+                FileDiff::Binary(change) => ChangedFile {
+                    path,
+                    stats: None,
+                    text: None,
+                    binary: Some(change),
+                },
+            };
+            Ok(file)
+        })
+        .collect()
+}
+
+pub fn handle(
+    cmd: FilesCommand,
+    config: &mut dyn Manager,
+    git: &dyn GitProvider,
+) -> Result<(), CommandError> {
+    let data = config.load()?;
+    let base = data.base_branch().ok_or_else(|| {
+        CommandError::InvalidInput(
+            "base_branchが設定されていません。mirudi init を先に実行してください".to_string(),
+        )
+    })?;
+    let target = git.get_current_branch()?;
+
+    if !cmd.stat {
+        for path in git.list_changed_files(&base, &target)? {
+            println!("{}", path);
+        }
+        return Ok(());
+    }
+
+    let files = list_changed_files_with_stats(git, &base, &target)?;
+    let width = files
+        .iter()
+        .map(|file| file.path.chars().count())
+        .max()
+        .unwrap_or(0);
+    for file in files {
+        let summary = match (&file.stats, &file.binary) {
+            (_, Some(change)) => change.to_string(),
+            (Some(stats), None) => stats.to_string(),
+            (None, None) => String::new(),
+        };
+        println!("{:<width$} | {}", file.path, summary, width = width);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::core::MockGitProvider;
+    use crate::utils::test_support::text_of;
+
+    fn text_blob(_: &str, path: &str, _: Option<SourceKind>) -> Result<BlobInfo, GitError> {
+        Ok(BlobInfo {
+            oid: format!("{:0>40}", path.len()),
+            size: path.len(),
+            binary: false,
+        })
+    }
+
+    #[test]
+    fn stats_treat_missing_side_as_empty() {
+        let mut git = MockGitProvider::new();
+        git.expect_blob_info().returning(text_blob);
+        git.expect_list_changed_files()
+            .returning(|_, _| Ok(vec!["added.rs".to_string(), "edited.rs".to_string()]));
+        git.expect_extract_text()
+            .returning(|branch, path, _| match (branch, path) {
+                ("main", "added.rs") => Err(GitError::FileNotFound),
+                ("main", _) => text_of(&["a", "b"]),
+                (_, "added.rs") => text_of(&["x", "y", "z"]),
+                _ => text_of(&["a", "b", "c"]),
+            });
+
+        let files = list_changed_files_with_stats(&git, "main", "feature").unwrap();
+
+        let stats = |file: &ChangedFile| {
+            let stats = file.stats.as_ref().unwrap();
+            (stats.added, stats.removed)
+        };
+        assert_eq!(files[0].path, "added.rs");
+        assert_eq!(stats(&files[0]), (3, 0));
+        assert_eq!(files[1].path, "edited.rs");
+        assert_eq!(stats(&files[1]), (1, 0));
+
+        let text = files[0].text.as_ref().unwrap();
+        assert_eq!(text.old, None);
+        assert_eq!(text.new.as_ref().unwrap().encoding, "UTF-8");
+    }
+
+    #[test]
+    fn load_file_diff_fails_when_both_sides_are_missing() {
+        let mut git = MockGitProvider::new();
+        git.expect_blob_info()
+            .returning(|_, _, _| Err(GitError::FileNotFound));
+
+        assert!(load_file_diff(&git, "main", "feature", "gone.rs").is_err());
+    }
+
+    #[test]
+    fn binary_files_are_summarized_without_extracting_lines() {
+        let mut git = MockGitProvider::new();
+        git.expect_list_changed_files()
+            .returning(|_, _| Ok(vec!["logo.png".to_string()]));
+        git.expect_blob_info().returning(|branch, _, _| {
+            if branch == "main" {
+                return Err(GitError::FileNotFound);
+            }
+            Ok(BlobInfo {
+                oid: "0123456789abcdef0123456789abcdef01234567".to_string(),
+                size: 2048,
+                binary: true,
+            })
+        });
+        git.expect_extract_text().never();
+
+        let files = list_changed_files_with_stats(&git, "main", "feature").unwrap();
+
+        assert_eq!(files[0].stats, None);
+        assert_eq!(files[0].text, None);
+        assert_eq!(
+            files[0].binary.as_ref().unwrap().to_string(),
+            "バイナリファイル: - → 2048 bytes, - → 0123456"
+        );
+    }
+
+    #[test]
+    fn errors_other_than_missing_file_are_not_treated_as_missing_side() {
+        let mut git = MockGitProvider::new();
+        git.expect_blob_info().returning(text_blob);
+        git.expect_extract_text()
+            .returning(|branch, _, _| match branch {
+                "main" => Err(GitError::InvalidEncoding("Shift_JIS".to_string())),
+                _ => text_of(&["a"]),
+            });
+
+        let result = load_file_diff(&git, "main", "feature", "legacy.txt");
+
+        assert!(matches!(result, Err(GitError::InvalidEncoding(_))));
+    }
+
+    #[test]
+    fn failed_blob_info_is_an_error() {
+        let mut git = MockGitProvider::new();
+        git.expect_blob_info()
+            .returning(|branch, path, source| match branch {
+                "main" => Err(GitError::InvalidObjectType),
+                _ => text_blob(branch, path, source),
+            });
+        git.expect_extract_text().never();
+
+        let result = load_file_diff(&git, "main", "feature", "a.rs");
+
+        assert!(matches!(result, Err(GitError::InvalidObjectType)));
+    }
+}
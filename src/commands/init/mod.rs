@@ -2,10 +2,12 @@ use clap::Args;
 use std::io::{BufRead, Write, stdin, stdout};
 
 use crate::commands::error::CommandError;
-use crate::config::Manager;
+use crate::config::{ConfigError, Manager};
+use crate::git::GitProvider;
 
 #[derive(Args)]
 pub struct InitCommand {
+    /// 比較の基準にするリビジョン。ブランチ名のほか、タグや SHA、HEAD~N なども指定できる
     #[arg(long)]
     pub base: Option<String>,
 }
@@ -34,13 +36,14 @@ pub fn prompt_base_branch() -> Result<String, CommandError> {
     prompt_for_input(
         &mut stdin,
         &mut stdout,
-        "デフォルトのブランチ名を教えてください",
+        "デフォルトのブランチ名 (タグや SHA も可) を教えてください",
     )
 }
 
 fn with_handle_init<F: Fn() -> Result<String, CommandError>>(
     cmd: InitCommand,
     config: &mut dyn Manager,
+    git: &dyn GitProvider,
     input_fn: F,
 ) -> Result<(), CommandError> {
     let branch = match &cmd.base {
@@ -51,23 +54,38 @@ fn with_handle_init<F: Fn() -> Result<String, CommandError>>(
         None => input_fn()?,
     };
 
-    let mut data = config.get_default()?;
+    // 存在しないリビジョンを保存して、後の ff などで初めて気づくことがないよう、ここで確かめる
+    let commit = git.resolve_revision(&branch)?;
+
+    // context や encodings など保存済みの設定は残し、base_branch だけを書き換える
+    let mut data = match config.load() {
+        Ok(data) => data,
+        Err(ConfigError::IoKind(std::io::ErrorKind::NotFound)) => config.get_default()?,
+        Err(e) => return Err(e.into()),
+    };
     data.set_base_branch(branch.clone())?;
 
     config.save(&data)?;
 
-    println!("base_branch を '{}' に設定しました", branch);
+    println!("base_branch を '{}' に設定しました ({})", branch, commit);
     Ok(())
 }
 
-pub fn handle(cmd: InitCommand, config: &mut dyn Manager) -> Result<(), CommandError> {
-    with_handle_init(cmd, config, prompt_base_branch)
+pub fn handle(
+    cmd: InitCommand,
+    config: &mut dyn Manager,
+    git: &dyn GitProvider,
+) -> Result<(), CommandError> {
+    with_handle_init(cmd, config, git, prompt_base_branch)
 }
 
 #[cfg(test)]
 mod tests {
     use crate::config::ConfigData;
     use crate::config::MockManager;
+    use crate::diff::ContextSize;
+    use crate::git::core::MockGitProvider;
+    use crate::git::{CommitInfo, GitError};
 
     use super::*;
 
@@ -76,14 +94,21 @@ mod tests {
     }
 
     fn mock_handle_init(cmd: InitCommand, config: &mut dyn Manager) -> Result<(), CommandError> {
-        with_handle_init(cmd, config, mock_prompt_for_input)
+        let mut git = MockGitProvider::new();
+        git.expect_resolve_revision().returning(|_| {
+            Ok(CommitInfo {
+                sha: "1a2b3c4d5e6f".to_string(),
+                subject: "Initial commit".to_string(),
+            })
+        });
+        with_handle_init(cmd, config, &git, mock_prompt_for_input)
     }
 
     #[test]
     fn test_handle_init_with_base_option() {
         let mut mock_manager = MockManager::new();
         mock_manager
-            .expect_get_default()
+            .expect_load()
             .returning(|| Ok(ConfigData::default()));
         mock_manager
             .expect_save()
@@ -101,7 +126,7 @@ mod tests {
     fn test_handle_init_with_empty_base_option() {
         let mut mock_manager = MockManager::new();
         mock_manager
-            .expect_get_default()
+            .expect_load()
             .returning(|| Ok(ConfigData::default()));
         mock_manager.expect_save().returning(|_| Ok(()));
         let cmd = InitCommand {
@@ -112,9 +137,73 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_handle_init_with_unknown_revision() {
+        let mut mock_manager = MockManager::new();
+        mock_manager.expect_save().never();
+        let mut git = MockGitProvider::new();
+        git.expect_resolve_revision()
+            .returning(|revision| Err(GitError::RevisionNotFound(revision.to_string())));
+        let cmd = InitCommand {
+            base: Some("v9.9.9".to_string()),
+        };
+
+        let err =
+            with_handle_init(cmd, &mut mock_manager, &git, mock_prompt_for_input).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Git エラー: 'v9.9.9' はブランチ・タグ・コミットのいずれとしても見つかりません"
+        );
+    }
+
     #[test]
     fn test_handle_init_with_none_base_option() {
         let mut mock_manager = MockManager::new();
+        mock_manager
+            .expect_load()
+            .returning(|| Ok(ConfigData::default()));
+        mock_manager
+            .expect_save()
+            .withf(|data| data.base_branch() == Some("test_branch".to_string()))
+            .returning(|_| Ok(()));
+        let cmd = InitCommand { base: None };
+        let result = mock_handle_init(cmd, &mut mock_manager);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_handle_init_keeps_other_settings() {
+        let mut mock_manager = MockManager::new();
+        mock_manager.expect_load().returning(|| {
+            Ok(serde_yaml::from_str(
+                "base_branch: develop\ncontext: 5\nencodings:\n  legacy/**: shift_jis\nignore_patterns:\n  - '^// generated'\n",
+            )
+            .unwrap())
+        });
+        mock_manager.expect_get_default().never();
+        mock_manager
+            .expect_save()
+            .withf(|data: &ConfigData| {
+                data.base_branch() == Some("test_branch".to_string())
+                    && data.context() == ContextSize::Lines(5)
+                    && data.encodings()["legacy/**"] == "shift_jis"
+                    && data.ignore_patterns() == vec!["^// generated"]
+            })
+            .returning(|_| Ok(()));
+        let cmd = InitCommand { base: None };
+        let result = mock_handle_init(cmd, &mut mock_manager);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_handle_init_without_config_file() {
+        let mut mock_manager = MockManager::new();
+        mock_manager
+            .expect_load()
+            .returning(|| Err(ConfigError::IoKind(std::io::ErrorKind::NotFound)));
         mock_manager
             .expect_get_default()
             .returning(|| Ok(ConfigData::default()));
@@ -127,4 +216,17 @@ mod tests {
 
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_handle_init_with_broken_config() {
+        let mut mock_manager = MockManager::new();
+        mock_manager
+            .expect_load()
+            .returning(|| Err(ConfigError::Yaml("invalid".to_string())));
+        mock_manager.expect_save().never();
+        let cmd = InitCommand { base: None };
+        let result = mock_handle_init(cmd, &mut mock_manager);
+
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,269 @@
+use clap::Args;
+use indexmap::IndexSet;
+use prettydiff::owo_colors::OwoColorize;
+use serde::Serialize;
+
+use crate::commands::error::CommandError;
+use crate::commands::files::{self, FileDiff};
+use crate::config::Manager;
+use crate::diff::{ContextSize, Diff, DiffProvider, LineDiff};
+use crate::git::{GitError, GitProvider};
+
+#[derive(Args)]
+pub struct InterdiffCommand {
+    /// 前回レビューした時点のリビジョン (SHA やブランチ名)
+    #[arg(value_name = "OLD_REVISION")]
+    pub old: String,
+
+    /// 新しいリビジョン。省略時は現在のブランチ
+    #[arg(value_name = "NEW_REVISION")]
+    pub new: Option<String>,
+
+    /// パッチの前後に含める行数 (full でファイル全体)。未指定なら設定値を使う
+    #[arg(short = 'U', long, value_name = "LINES")]
+    pub context: Option<ContextSize>,
+}
+
+/// 2 つの版のパッチの違い
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum InterdiffStatus {
+    /// どちらの版でも同じ変更
+    Unchanged,
+    /// 両方の版で変更しているが、変更内容が異なる
+    Changed,
+    /// 新しい版で初めて変更された
+    Added,
+    /// 新しい版では変更しなくなった
+    Dropped,
+}
+
+impl InterdiffStatus {
+    fn marker(&self) -> char {
+        match self {
+            InterdiffStatus::Unchanged => '=',
+            InterdiffStatus::Changed => '!',
+            InterdiffStatus::Added => '+',
+            InterdiffStatus::Dropped => '-',
+        }
+    }
+}
+
+/// ファイルごとのパッチの差分。`lines` は old / new それぞれの版のパッチの行を比べたもの
+#[derive(Serialize)]
+pub struct InterdiffFile {
+    pub path: String,
+    pub status: InterdiffStatus,
+    pub lines: Vec<LineDiff>,
+}
+
+/// 同じブランチの 2 つの版を、それぞれのマージベースからのパッチ同士で比べる (git range-diff のファイル単位版)。
+/// リベースでベースが進んでいても、ブランチ自身の変更の違いだけが残る
+pub fn interdiff(
+    git: &dyn GitProvider,
+    base_branch: &str,
+    (old_revision, new_revision): (&str, &str),
+    context: ContextSize,
+) -> Result<Vec<InterdiffFile>, GitError> {
+    let old_base = git.merge_base(base_branch, old_revision)?;
+    let new_base = git.merge_base(base_branch, new_revision)?;
+    let old_files = git.list_changed_files(&old_base, old_revision)?;
+    let new_files = git.list_changed_files(&new_base, new_revision)?;
+
+    let paths: IndexSet<&String> = new_files.iter().chain(&old_files).collect();
+    paths
+        .into_iter()
+        .map(|path| {
+            let old_patch = old_files
+                .contains(path)
+                .then(|| patch(git, (&old_base, old_revision), path, context))
+                .transpose()?;
+            let new_patch = new_files
+                .contains(path)
+                .then(|| patch(git, (&new_base, new_revision), path, context))
+                .transpose()?;
+
+            let status = match (&old_patch, &new_patch) {
+                (None, _) => InterdiffStatus::Added,
+                (_, None) => InterdiffStatus::Dropped,
+                (Some(old), Some(new)) if old == new => InterdiffStatus::Unchanged,
+                _ => InterdiffStatus::Changed,
+            };
+            let lines = Diff::new(old_patch.unwrap_or_default(), new_patch.unwrap_or_default())
+                .with_context(context)
+                .lines_structured();
+
+            Ok(InterdiffFile {
+                path: path.clone(),
+                status,
+                lines,
+            })
+        })
+        .collect()
+}
+
+/// 行番号を除いたパッチ。バイナリファイルは大きさと OID の変化を 1 行にする
+fn patch(
+    git: &dyn GitProvider,
+    (base, revision): (&str, &str),
+    path: &str,
+    context: ContextSize,
+) -> Result<Vec<String>, GitError> {
+    let patch = match files::load_file_diff(git, base, revision, path)? {
+        FileDiff::Text(diff, _) => diff.with_context(context).patch_lines(),
+        FileDiff::Binary(change) => vec![change.to_string()],
+    };
+    Ok(patch)
+}
+
+pub fn handle(
+    cmd: InterdiffCommand,
+    config: &mut dyn Manager,
+    git: &dyn GitProvider,
+) -> Result<(), CommandError> {
+    let data = config.load()?;
+    let base = data.base_branch().ok_or_else(|| {
+        CommandError::InvalidInput(
+            "base_branchが設定されていません。mirudi init を先に実行してください".to_string(),
+        )
+    })?;
+    let new = match cmd.new {
+        Some(new) => new,
+        None => git.get_current_branch()?,
+    };
+    let context = cmd.context.unwrap_or(data.context());
+
+    for file in interdiff(git, &base, (&cmd.old, &new), context)? {
+        let title = format!("{} {}", file.status.marker(), file.path);
+        match file.status {
+            InterdiffStatus::Unchanged => println!("{}", title.dimmed()),
+            InterdiffStatus::Changed => println!("{}", title.yellow()),
+            InterdiffStatus::Added => println!("{}", title.green()),
+            InterdiffStatus::Dropped => println!("{}", title.red()),
+        }
+        if file.status == InterdiffStatus::Changed {
+            println!("{}", render(&file.lines));
+        }
+    }
+    Ok(())
+}
+
+/// パッチの行の前に、その行が古い版 (-) と新しい版 (+) のどちらにあるかを付ける
+fn render(lines: &[LineDiff]) -> String {
+    let mut out = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+
+    for line in lines {
+        if !line.is_change() {
+            out.append(&mut removed);
+            out.append(&mut added);
+            if let Some(old) = &line.old {
+                out.push(format!("    {}", old.content));
+            }
+            continue;
+        }
+        if let Some(old) = &line.old {
+            removed.push(format!("  - {}", old.content).red().to_string());
+        }
+        if let Some(new) = &line.new {
+            added.push(format!("  + {}", new.content).green().to_string());
+        }
+    }
+    out.append(&mut removed);
+    out.append(&mut added);
+
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::BlobInfo;
+    use crate::git::core::MockGitProvider;
+    use crate::utils::test_support::text_of;
+
+    /// v1 と、リベースした v2 は、それぞれ base-of-v1 / base-of-v2 から分岐している
+    fn setup_git() -> MockGitProvider {
+        let mut git = MockGitProvider::new();
+        git.expect_merge_base()
+            .returning(|_, revision| Ok(format!("base-of-{revision}")));
+        git.expect_list_changed_files()
+            .returning(|_, revision| match revision {
+                "v1" => Ok(vec!["same.rs".to_string(), "edited.rs".to_string()]),
+                _ => Ok(vec![
+                    "same.rs".to_string(),
+                    "edited.rs".to_string(),
+                    "new.rs".to_string(),
+                ]),
+            });
+        git.expect_blob_info().returning(|_, path, _| {
+            Ok(BlobInfo {
+                oid: format!("{:0>40}", path.len()),
+                size: path.len(),
+                binary: false,
+            })
+        });
+        git.expect_extract_text()
+            .returning(|revision, path, _| match (revision, path) {
+                // ベース側はリベースで先頭に行が増えているが、ブランチの変更は同じ
+                ("base-of-v1", "same.rs") => text_of(&["a", "b"]),
+                ("v1", "same.rs") => text_of(&["a", "B"]),
+                ("base-of-v2", "same.rs") => text_of(&["// x", "// y", "// z", "a", "b"]),
+                ("v2", "same.rs") => text_of(&["// x", "// y", "// z", "a", "B"]),
+                (_, "edited.rs") if revision.starts_with("base") => text_of(&["fn f() {}"]),
+                ("v1", "edited.rs") => text_of(&["fn f() { 1 }"]),
+                ("v2", "edited.rs") => text_of(&["fn f() { 2 }"]),
+                ("v2", "new.rs") => text_of(&["new"]),
+                _ => Err(GitError::FileNotFound),
+            });
+        git
+    }
+
+    #[test]
+    fn compares_patches_instead_of_contents() {
+        let git = setup_git();
+
+        let files = interdiff(&git, "main", ("v1", "v2"), ContextSize::Lines(1)).unwrap();
+
+        let summary: Vec<_> = files
+            .iter()
+            .map(|file| (file.path.as_str(), file.status))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("same.rs", InterdiffStatus::Unchanged),
+                ("edited.rs", InterdiffStatus::Changed),
+                ("new.rs", InterdiffStatus::Added),
+            ]
+        );
+        assert!(files[0].lines.is_empty());
+
+        let changed: Vec<_> = files[1]
+            .lines
+            .iter()
+            .filter(|line| line.is_change())
+            .map(|line| {
+                (
+                    line.old.as_ref().map(|l| l.content.as_str()),
+                    line.new.as_ref().map(|l| l.content.as_str()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changed,
+            vec![(Some("+fn f() { 1 }"), Some("+fn f() { 2 }"))]
+        );
+    }
+
+    #[test]
+    fn files_no_longer_changed_are_dropped() {
+        let git = setup_git();
+
+        let files = interdiff(&git, "main", ("v2", "v1"), ContextSize::default()).unwrap();
+
+        assert_eq!(files.last().unwrap().path, "new.rs");
+        assert_eq!(files.last().unwrap().status, InterdiffStatus::Dropped);
+    }
+}
@@ -1,7 +1,15 @@
+mod config;
+pub use config::ConfigCommand;
+mod export;
+pub use export::ExportCommand;
 pub mod init;
 pub use init::InitCommand;
 pub mod ff;
 pub use ff::FFCommand;
+mod files;
+pub use files::FilesCommand;
+mod interdiff;
+pub use interdiff::InterdiffCommand;
 mod scope;
 pub use scope::ScopeCommand;
 mod web;
@@ -19,8 +27,12 @@ use error::CommandError;
 
 #[derive(Subcommand)]
 pub enum CliCommands {
-    FF(FFCommand),
+    FF(Box<FFCommand>),
     Init(InitCommand),
+    Config(ConfigCommand),
+    Export(ExportCommand),
+    Files(FilesCommand),
+    Interdiff(InterdiffCommand),
     #[command(alias = "sc")]
     Scope(ScopeCommand),
 }
@@ -45,8 +57,12 @@ pub fn handle_cli_command(
     git: &dyn GitProvider,
 ) -> Result<(), CommandError> {
     match command {
-        CliCommands::FF(cmd) => ff::handle(cmd, config, git),
-        CliCommands::Init(cmd) => init::handle(cmd, config),
+        CliCommands::FF(cmd) => ff::handle(*cmd, config, git),
+        CliCommands::Init(cmd) => init::handle(cmd, config, git),
+        CliCommands::Config(cmd) => config::handle(cmd, config),
+        CliCommands::Export(cmd) => export::handle(cmd, config, git),
+        CliCommands::Files(cmd) => files::handle(cmd, config, git),
+        CliCommands::Interdiff(cmd) => interdiff::handle(cmd, config, git),
         CliCommands::Scope(cmd) => scope::handle(cmd, config, git),
     }
 }
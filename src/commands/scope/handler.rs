@@ -1,6 +1,6 @@
 use crate::commands::error::CommandError;
 use crate::config::{ConfigData, ConfigScopeInput, Manager};
-use crate::git::GitProvider;
+use crate::git::{CommitInfo, GitProvider};
 
 use super::core::{ScopeCommand, ScopeInputResolver};
 use super::prompt_input::PromptInputRunner;
@@ -10,10 +10,12 @@ type ConfigManagerMut<'a> = &'a mut dyn Manager;
 type MaybeGitOps<'a> = Option<&'a dyn GitProvider>;
 type PromptInputFn<'a> = Box<dyn Runner + 'a>;
 type BranchNameFetcher<'a> = Box<dyn Fn() -> Result<String, CommandError> + 'a>;
+type RevisionResolver<'a> = Box<dyn Fn(&str) -> Result<CommitInfo, CommandError> + 'a>;
 
 pub struct Deps<'a> {
     pub prompt_input: PromptInputFn<'a>,
     pub get_current_branch: BranchNameFetcher<'a>,
+    pub resolve_revision: RevisionResolver<'a>,
 }
 
 pub struct DepsBuilder<'a> {
@@ -37,11 +39,16 @@ impl<'a> DepsBuilder<'a> {
         let get_current_branch =
             Box::new(move || git.get_current_branch().map_err(CommandError::Git));
 
+        let resolve_revision = Box::new(move |revision: &str| {
+            git.resolve_revision(revision).map_err(CommandError::Git)
+        });
+
         let prompt_input = Box::new(PromptInputRunner::new(git)) as Box<dyn Runner>;
 
         Ok(Deps {
             prompt_input,
             get_current_branch,
+            resolve_revision,
         })
     }
 }
@@ -51,6 +58,7 @@ pub struct HandleBuilder<'a> {
     config: Option<ConfigManagerMut<'a>>,
     prompt_input: Option<PromptInputFn<'a>>,
     get_current_branch_name: Option<BranchNameFetcher<'a>>,
+    resolve_revision: Option<RevisionResolver<'a>>,
     no_display: Option<bool>,
 }
 
@@ -61,6 +69,7 @@ impl<'a> HandleBuilder<'a> {
             config: None,
             prompt_input: None,
             get_current_branch_name: None,
+            resolve_revision: None,
             no_display: None,
         }
     }
@@ -88,6 +97,14 @@ impl<'a> HandleBuilder<'a> {
         self
     }
 
+    pub fn resolve_revision<F>(mut self, resolve_fn: F) -> Self
+    where
+        F: Fn(&str) -> Result<CommitInfo, CommandError> + 'a,
+    {
+        self.resolve_revision = Some(Box::new(resolve_fn));
+        self
+    }
+
     pub fn no_display(mut self, display: bool) -> Self {
         self.no_display = Some(display);
         self
@@ -109,6 +126,9 @@ impl<'a> HandleBuilder<'a> {
                 .ok_or(CommandError::InternalError(
                     "ブランチ名取得関数が指定されていません".to_string(),
                 ))?;
+        let resolve_revision = self.resolve_revision.ok_or(CommandError::InternalError(
+            "リビジョン解決関数が指定されていません".to_string(),
+        ))?;
         let no_display = self.no_display.unwrap_or(false);
 
         Ok(Handler {
@@ -116,6 +136,7 @@ impl<'a> HandleBuilder<'a> {
             config,
             prompt_input,
             get_current_branch_name,
+            resolve_revision,
             no_display,
         })
     }
@@ -126,6 +147,7 @@ pub struct Handler<'a> {
     config: ConfigManagerMut<'a>,
     prompt_input: PromptInputFn<'a>,
     get_current_branch_name: BranchNameFetcher<'a>,
+    resolve_revision: RevisionResolver<'a>,
     no_display: bool,
 }
 
@@ -133,6 +155,7 @@ impl Handler<'_> {
     pub fn exec(&mut self) -> Result<(), CommandError> {
         let mut data = self.get_current_data()?;
         let input = self.get_input()?;
+        self.validate_input(&input)?;
         data.set_scope(input);
         self.save_data(&data)?;
         let new_data = self.get_current_data()?;
@@ -159,6 +182,14 @@ impl Handler<'_> {
         }
     }
 
+    /// 比較対象はブランチ名に限らず、タグや SHA なども指定できる。解決できないリビジョンは保存しない
+    fn validate_input(&self, input: &ConfigScopeInput) -> Result<(), CommandError> {
+        if let Some(revision) = &input.current_branch {
+            (self.resolve_revision)(revision)?;
+        }
+        Ok(())
+    }
+
     fn save_data(&mut self, data: &ConfigData) -> Result<(), CommandError> {
         self.config.save(data)?;
         Ok(())
@@ -170,10 +201,14 @@ impl Handler<'_> {
         }
 
         println!("\n設定が完了しました！");
-        println!(
-            "- ブランチ: {}",
-            new_data.current_branch().as_deref().unwrap_or("未設定")
-        );
+        let branch = match new_data.current_branch() {
+            Some(branch) => match (self.resolve_revision)(&branch) {
+                Ok(commit) => format!("{} ({})", branch, commit),
+                Err(_) => branch,
+            },
+            None => "未設定".to_string(),
+        };
+        println!("- ブランチ: {}", branch);
         println!(
             "- 古いパス: {}",
             new_data.old_file_path().as_deref().unwrap_or("未設定")
@@ -193,8 +228,18 @@ mod tests {
     use crate::config::ConfigData;
     use crate::config::ConfigError;
     use crate::config::MockManager;
+    use crate::git::GitError;
     use crate::git::core::MockGitProvider;
 
+    fn resolve_any() -> RevisionResolver<'static> {
+        Box::new(|revision| {
+            Ok(CommitInfo {
+                sha: format!("{:0>40}", revision.len()),
+                subject: "subject".to_string(),
+            })
+        })
+    }
+
     mod deps_builder {
         use super::*;
 
@@ -233,6 +278,7 @@ mod tests {
                 .config(&mut config)
                 .prompt_input(prompt_input)
                 .get_current_branch_name(get_current_branch_name)
+                .resolve_revision(resolve_any())
                 .build();
 
             assert!(result.is_ok());
@@ -248,6 +294,7 @@ mod tests {
                 .config(&mut config)
                 .prompt_input(prompt_input)
                 .get_current_branch_name(get_current_branch_name)
+                .resolve_revision(resolve_any())
                 .build();
 
             assert!(result.is_err());
@@ -268,6 +315,7 @@ mod tests {
                 .cmd(cmd)
                 .prompt_input(prompt_input)
                 .get_current_branch_name(get_current_branch_name)
+                .resolve_revision(resolve_any())
                 .build();
             assert!(result.is_err());
         }
@@ -287,6 +335,28 @@ mod tests {
                 .cmd(cmd)
                 .config(&mut config)
                 .get_current_branch_name(get_current_branch_name)
+                .resolve_revision(resolve_any())
+                .build();
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn test_build_without_resolve_revision() {
+            let cmd = ScopeCommand {
+                current: true,
+                branch: None,
+                old: None,
+                new: None,
+                path: None,
+            };
+            let mut config = MockManager::new();
+            let prompt_input = Box::new(MockRunner::new());
+            let get_current_branch_name = Box::new(|| Ok("test_branch".to_string()));
+            let result = HandleBuilder::new()
+                .cmd(cmd)
+                .config(&mut config)
+                .prompt_input(prompt_input)
+                .get_current_branch_name(get_current_branch_name)
                 .build();
             assert!(result.is_err());
         }
@@ -353,6 +423,7 @@ mod tests {
                 config: &mut config,
                 prompt_input,
                 get_current_branch_name,
+                resolve_revision: resolve_any(),
                 no_display: true,
             };
             let result = handler.exec();
@@ -373,6 +444,7 @@ mod tests {
                 config: &mut config,
                 prompt_input,
                 get_current_branch_name,
+                resolve_revision: resolve_any(),
                 no_display: true,
             };
             let result = handler.get_current_data();
@@ -391,6 +463,7 @@ mod tests {
                 config: &mut config,
                 prompt_input,
                 get_current_branch_name,
+                resolve_revision: resolve_any(),
                 no_display: true,
             };
             let result = handler.get_current_data();
@@ -406,6 +479,7 @@ mod tests {
                 config: &mut config,
                 prompt_input,
                 get_current_branch_name,
+                resolve_revision: resolve_any(),
                 no_display: true,
             };
 
@@ -441,12 +515,42 @@ mod tests {
                 config: &mut config,
                 prompt_input: boxed_prompt_input,
                 get_current_branch_name,
+                resolve_revision: resolve_any(),
                 no_display: true,
             };
             let result = handler.get_input();
             assert!(result.is_ok());
         }
 
+        #[test]
+        fn test_exec_rejects_unknown_revision() {
+            let (_, mut config, prompt_input, get_current_branch_name) = setup();
+            let cmd = ScopeCommand {
+                current: false,
+                branch: Some("no-such-tag".to_string()),
+                old: None,
+                new: None,
+                path: None,
+            };
+            config.expect_load().returning(|| Ok(ConfigData::default()));
+            config.expect_save().never();
+
+            let mut handler = Handler {
+                cmd,
+                config: &mut config,
+                prompt_input,
+                get_current_branch_name,
+                resolve_revision: Box::new(|revision| {
+                    Err(CommandError::Git(GitError::RevisionNotFound(
+                        revision.to_string(),
+                    )))
+                }),
+                no_display: true,
+            };
+
+            assert!(handler.exec().is_err());
+        }
+
         #[test]
         fn test_save_data_ok() {
             let (cmd, mut config, prompt_input, get_current_branch_name) = setup();
@@ -459,6 +563,7 @@ mod tests {
                 config: &mut config,
                 prompt_input,
                 get_current_branch_name,
+                resolve_revision: resolve_any(),
                 no_display: true,
             };
             let result = handler.save_data(&data);
@@ -480,6 +585,7 @@ mod tests {
                 config: &mut config,
                 prompt_input,
                 get_current_branch_name,
+                resolve_revision: resolve_any(),
                 no_display: true,
             };
             let result = handler.save_data(&data);
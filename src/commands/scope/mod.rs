@@ -20,6 +20,7 @@ pub fn run_scope_silently(
         .config(config)
         .prompt_input(deps.prompt_input)
         .get_current_branch_name(deps.get_current_branch)
+        .resolve_revision(deps.resolve_revision)
         .no_display(true)
         .build()?;
 
@@ -38,6 +39,7 @@ pub fn handle(
         .config(config)
         .prompt_input(deps.prompt_input)
         .get_current_branch_name(deps.get_current_branch)
+        .resolve_revision(deps.resolve_revision)
         .build()?;
 
     handler.exec()?;
@@ -50,6 +52,7 @@ mod tests {
 
     use crate::config::ConfigData;
     use crate::config::MockManager;
+    use crate::git::CommitInfo;
     use crate::git::core::MockGitProvider;
 
     #[test]
@@ -69,6 +72,12 @@ mod tests {
 
         git.expect_get_current_branch()
             .returning(|| Ok("test_branch".to_string()));
+        git.expect_resolve_revision().returning(|_| {
+            Ok(CommitInfo {
+                sha: "1a2b3c4d5e6f".to_string(),
+                subject: "subject".to_string(),
+            })
+        });
 
         let result = handle(cmd, &mut config, &git);
         assert!(result.is_ok());
@@ -91,6 +100,12 @@ mod tests {
 
         git.expect_get_current_branch()
             .returning(|| Ok("test_branch".to_string()));
+        git.expect_resolve_revision().returning(|_| {
+            Ok(CommitInfo {
+                sha: "1a2b3c4d5e6f".to_string(),
+                subject: "subject".to_string(),
+            })
+        });
         let result = run_scope_silently(cmd, &mut config, &git);
         assert!(result.is_ok());
     }
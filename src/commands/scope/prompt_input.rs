@@ -28,6 +28,7 @@ impl<'a> PromptInputRunner<'a> {
         let current_branch_option = format!("現在のブランチ: {}", current_branch);
         let mut display_branches = vec![current_branch_option];
         display_branches.extend(branches.clone());
+        display_branches.push("リビジョンを入力 (タグ・SHA・HEAD~N など)".to_string());
 
         let branch_idx = FuzzySelect::new()
             .with_prompt("ブランチを選択してください")
@@ -36,13 +37,28 @@ impl<'a> PromptInputRunner<'a> {
             .interact()
             .map_err(|_| CommandError::InvalidInput("無効なブランチ選択です".to_string()))?;
 
-        if branch_idx == 0 {
-            Ok(Some(current_branch))
-        } else {
-            Ok(Some(branches[branch_idx - 1].clone()))
+        match branch_idx {
+            0 => Ok(Some(current_branch)),
+            i if i <= branches.len() => Ok(Some(branches[i - 1].clone())),
+            _ => self.prompt_revision(git).map(Some),
         }
     }
 
+    /// 入力のたびにリビジョンを解決し、見つからなければその場で入力し直してもらう
+    fn prompt_revision(&self, git: &dyn GitProvider) -> Result<String, CommandError> {
+        let revision = Input::<String>::new()
+            .with_prompt("リビジョンを入力してください")
+            .validate_with(|input: &String| -> Result<(), String> {
+                git.resolve_revision(input.trim())
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            })
+            .interact()
+            .map_err(|e| CommandError::InvalidInput(format!("無効なリビジョン入力です: {}", e)))?;
+
+        Ok(revision.trim().to_string())
+    }
+
     fn prompt_path(&self, prompt_message: &str) -> Result<Option<String>, CommandError> {
         let path = Input::<String>::new()
             .with_prompt(prompt_message)
@@ -1,7 +1,9 @@
 use super::assets::WebAssets;
+use crate::commands::files::{self, BinaryChange, FileDiff, TextChange};
+use crate::commands::interdiff;
 use crate::config::Manager;
-use crate::diff::{Diff, DiffProvider};
-use crate::git::{GitProvider, core::SourceKind};
+use crate::diff::{ContextSize, Diff, DiffAlgorithm, DiffProvider, IgnorePatterns, WhitespaceMode};
+use crate::git::{GitError, GitProvider, core::SourceKind};
 use axum::Json;
 use axum::extract::{Query, State};
 use axum::{
@@ -13,8 +15,10 @@ use axum::{
 };
 
 use std::collections::HashMap;
+use std::fmt::Display;
 use std::io;
 use std::net::SocketAddr;
+use std::str::FromStr;
 use std::sync::Arc;
 use tokio::sync::Notify;
 use tower_http::cors::{AllowOrigin, CorsLayer};
@@ -24,6 +28,10 @@ pub struct WebServerState {
     pub git: Arc<dyn GitProvider + Send + Sync>,
     pub base_branch: String,
     pub target_branch: String,
+    /// context クエリを省略したときの文脈行数
+    pub context: ContextSize,
+    /// 設定の ignore_patterns。ignore_matching クエリはこれに追加される
+    pub ignore_patterns: Vec<String>,
 }
 
 pub async fn start_server(
@@ -31,25 +39,25 @@ pub async fn start_server(
     config: &mut dyn Manager,
     git: Arc<dyn GitProvider + Send + Sync>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let data = config.load().map_err(|e| {
-        io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to load config: {}", e),
-        )
-    })?;
+    let data = config
+        .load()
+        .map_err(|e| io::Error::other(format!("Failed to load config: {}", e)))?;
     let base = data.base_branch().ok_or_else(|| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            "base_branchが設定されていません。mirudi init を先に実行してください",
-        )
+        io::Error::other("base_branchが設定されていません。mirudi init を先に実行してください")
     })?;
 
     let target = git.get_current_branch()?;
 
+    let ignore_patterns = data.ignore_patterns();
+    IgnorePatterns::new(&ignore_patterns)
+        .map_err(|e| io::Error::other(format!("設定の ignore_patterns が不正です: {}", e)))?;
+
     let state = WebServerState {
         git,
         base_branch: base,
         target_branch: target,
+        context: data.context(),
+        ignore_patterns,
     };
 
     let state = Arc::new(state);
@@ -59,7 +67,10 @@ pub async fn start_server(
 
     let api_routes = Router::new()
         .route("/files", get(get_changed_files))
-        .route("/diff", get(get_diff));
+        .route("/diff", get(get_diff))
+        .route("/v2/diff", get(get_diff_hunks))
+        .route("/three-way", get(get_three_way))
+        .route("/interdiff", get(get_interdiff));
 
     let origins = vec![
         HeaderValue::from_str("http://localhost:4321").unwrap(),
@@ -132,12 +143,23 @@ async fn static_handler(uri: Uri) -> impl IntoResponse {
     }
 }
 
-async fn get_changed_files(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
-    let base = &state.base_branch;
+/// `merge_base=true` なら、ベースブランチの先端ではなくマージベースからの変更を一覧にする
+async fn get_changed_files(
+    State(state): State<Arc<WebServerState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let merge_base = match parse_query(&params, "merge_base") {
+        Ok(merge_base) => merge_base.unwrap_or_default(),
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let base = match old_revision(&state, merge_base) {
+        Ok(base) => base,
+        Err(e) => return e.into_response(),
+    };
     let target = &state.target_branch;
     let git = state.git.clone();
 
-    match git.list_changed_files(base, target) {
+    match files::list_changed_files_with_stats(git.as_ref(), &base, target) {
         Ok(files) => Json(files).into_response(),
         Err(e) => {
             eprintln!("エラー発生: {:?}", e);
@@ -146,37 +168,343 @@ async fn get_changed_files(State(state): State<Arc<WebServerState>>) -> impl Int
     }
 }
 
+/// 互換性のため、テキストファイルは行の配列だけを返す。文字コードと改行コードは `/api/v2/diff` で返す
 async fn get_diff(
     State(state): State<Arc<WebServerState>>,
     Query(params): Query<HashMap<String, String>>,
 ) -> impl IntoResponse {
-    let Some(file_path) = params.get("file") else {
-        return (StatusCode::BAD_REQUEST, "file クエリが必要").into_response();
-    };
+    match load_diff(&state, &params) {
+        Ok(FileDiff::Text(diff, _)) => Json(diff.lines_structured()).into_response(),
+        Ok(FileDiff::Binary(change)) => Json(BinaryResponse { binary: change }).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
 
-    let base = &state.base_branch;
-    let target = &state.target_branch;
-    let git = state.git.clone();
+/// `/api/diff` と同じクエリで、文脈ごとのハンクに分けた差分を返す
+async fn get_diff_hunks(
+    State(state): State<Arc<WebServerState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    match load_diff(&state, &params) {
+        Ok(FileDiff::Text(diff, text)) => Json(TextResponse {
+            text,
+            diff: diff.hunks(),
+        })
+        .into_response(),
+        Ok(FileDiff::Binary(change)) => Json(BinaryResponse { binary: change }).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
 
-    let old_lines = git.extract_lines(base, file_path, 1, usize::MAX, Some(SourceKind::Commit));
-    let new_lines = git.extract_lines(target, file_path, 1, usize::MAX, Some(SourceKind::Commit));
+/// マージベース・ベースブランチ・比較対象の 3 列で、どちらのブランチの変更かを付けて返す
+async fn get_three_way(
+    State(state): State<Arc<WebServerState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let mut params = params;
+    // 3 列のうち base はブランチの先端を表示するため、merge_base クエリは使わない
+    params.remove("merge_base");
 
-    match (old_lines, new_lines) {
-        (Ok(old), Ok(new)) => {
-            let diff = Diff::new(old, new).lines_structured();
-            Json(diff).into_response()
+    let (diff, text) = match load_diff(&state, &params) {
+        Ok(FileDiff::Text(diff, text)) => (diff, text),
+        Ok(FileDiff::Binary(change)) => {
+            return Json(BinaryResponse { binary: change }).into_response();
         }
-        (Ok(old), Err(_)) => {
-            let diff = Diff::new(old, vec![]).lines_structured();
-            Json(diff).into_response()
+        Err(e) => return e.into_response(),
+    };
+
+    match load_merge_base_lines(&state, &params["file"]) {
+        Ok(merge_base) => Json(TextResponse {
+            text,
+            diff: diff.three_way(merge_base).lines_structured(),
+        })
+        .into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// `old` の版から `new` の版 (省略時は現在のブランチ) までに、ブランチの変更がどう変わったかを返す
+async fn get_interdiff(
+    State(state): State<Arc<WebServerState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let Some(old) = params.get("old") else {
+        return (StatusCode::BAD_REQUEST, "old クエリが必要").into_response();
+    };
+    let new = params.get("new").unwrap_or(&state.target_branch);
+    let context = match parse_query(&params, "context") {
+        Ok(context) => context.unwrap_or(state.context),
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    match interdiff::interdiff(state.git.as_ref(), &state.base_branch, (old, new), context) {
+        Ok(files) => Json(files).into_response(),
+        Err(e) => {
+            eprintln!("エラー発生: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "interdiff取得失敗").into_response()
         }
-        (Err(_), Ok(new)) => {
-            let diff = Diff::new(vec![], new).lines_structured();
-            Json(diff).into_response()
+    }
+}
+
+/// ブランチの分岐後に作られたファイルは、マージベースでは空として扱う
+fn load_merge_base_lines(
+    state: &WebServerState,
+    file_path: &str,
+) -> Result<Vec<String>, (StatusCode, String)> {
+    let merge_base = old_revision(state, true)?;
+    match state
+        .git
+        .extract_text(&merge_base, file_path, Some(SourceKind::Commit))
+    {
+        Ok(text) => Ok(text.lines),
+        Err(GitError::FileNotFound) => Ok(vec![]),
+        Err(e) => {
+            eprintln!("マージベースのファイル取得失敗: {}: {}", file_path, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "diff取得失敗".to_string(),
+            ))
         }
-        (Err(_), Err(_)) => {
+    }
+}
+
+/// 旧側に使うリビジョン。`merge_base` ならベースブランチとの分岐点になる
+fn old_revision(state: &WebServerState, merge_base: bool) -> Result<String, (StatusCode, String)> {
+    if !merge_base {
+        return Ok(state.base_branch.clone());
+    }
+    state
+        .git
+        .merge_base(&state.base_branch, &state.target_branch)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+fn load_diff(
+    state: &WebServerState,
+    params: &HashMap<String, String>,
+) -> Result<FileDiff, (StatusCode, String)> {
+    let Some(file_path) = params.get("file") else {
+        return Err((StatusCode::BAD_REQUEST, "file クエリが必要".to_string()));
+    };
+
+    let options =
+        DiffOptions::from_query(params, state).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let base = old_revision(state, options.merge_base)?;
+
+    let diff = files::load_file_diff(state.git.as_ref(), &base, &state.target_branch, file_path)
+        .map_err(|_| {
             eprintln!("ファイルのdiff取得失敗: {}", file_path);
-            (StatusCode::INTERNAL_SERVER_ERROR, "diff取得失敗").into_response()
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "diff取得失敗".to_string(),
+            )
+        })?;
+
+    Ok(match diff {
+        FileDiff::Text(diff, change) => FileDiff::Text(options.apply(diff), change),
+        binary => binary,
+    })
+}
+
+/// テキストファイルの差分に、両側の文字コードと改行コードを添えて返す
+#[derive(serde::Serialize)]
+struct TextResponse<T> {
+    text: TextChange,
+    diff: T,
+}
+
+/// バイナリファイルは行ごとの差分の代わりに、大きさと OID の変化を返す
+#[derive(serde::Serialize)]
+struct BinaryResponse {
+    binary: BinaryChange,
+}
+
+/// `/api/diff` のクエリで指定できる比較オプション
+struct DiffOptions {
+    algorithm: DiffAlgorithm,
+    whitespace: WhitespaceMode,
+    ignore_blank_lines: bool,
+    ignore_cr_at_eol: bool,
+    /// 設定の ignore_patterns と ignore_matching クエリを合わせたもの
+    ignore_patterns: IgnorePatterns,
+    context: ContextSize,
+    /// ベースブランチの先端ではなくマージベースと比較する
+    merge_base: bool,
+}
+
+impl DiffOptions {
+    /// 省略されたクエリは、サーバー起動時に読み込んだ設定値を使う
+    fn from_query(
+        params: &HashMap<String, String>,
+        defaults: &WebServerState,
+    ) -> Result<Self, String> {
+        let ignore_patterns = defaults
+            .ignore_patterns
+            .iter()
+            .chain(params.get("ignore_matching"));
+        Ok(Self {
+            algorithm: parse_query(params, "algorithm")?.unwrap_or_default(),
+            whitespace: parse_query(params, "whitespace")?.unwrap_or_default(),
+            ignore_blank_lines: parse_query(params, "ignore_blank_lines")?.unwrap_or_default(),
+            ignore_cr_at_eol: parse_query(params, "ignore_cr_at_eol")?.unwrap_or_default(),
+            ignore_patterns: IgnorePatterns::new(ignore_patterns)
+                .map_err(|e| format!("ignore_matching クエリが不正です: {}", e))?,
+            context: parse_query(params, "context")?.unwrap_or(defaults.context),
+            merge_base: parse_query(params, "merge_base")?.unwrap_or_default(),
+        })
+    }
+
+    fn apply(&self, diff: Diff) -> Diff {
+        diff.with_algorithm(self.algorithm)
+            .with_whitespace(self.whitespace)
+            .with_ignore_blank_lines(self.ignore_blank_lines)
+            .with_ignore_cr_at_eol(self.ignore_cr_at_eol)
+            .with_ignore_patterns(self.ignore_patterns.clone())
+            .with_context(self.context)
+    }
+}
+
+fn parse_query<T>(params: &HashMap<String, String>, key: &str) -> Result<Option<T>, String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    params
+        .get(key)
+        .map(|value| {
+            value
+                .parse::<T>()
+                .map_err(|e| format!("{} クエリが不正です: {}", key, e))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::core::MockGitProvider;
+    use crate::git::{BlobInfo, LineEnding};
+    use crate::utils::test_support::text_in;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn state(context: ContextSize, ignore_patterns: &[&str]) -> WebServerState {
+        WebServerState {
+            git: Arc::new(MockGitProvider::new()),
+            base_branch: "main".to_string(),
+            target_branch: "feature".to_string(),
+            context,
+            ignore_patterns: ignore_patterns.iter().map(|p| p.to_string()).collect(),
         }
     }
+
+    #[test]
+    fn diff_options_default_when_absent() {
+        let options = DiffOptions::from_query(
+            &params(&[("file", "a.rs")]),
+            &state(ContextSize::Lines(5), &[]),
+        )
+        .unwrap();
+        assert_eq!(options.algorithm, DiffAlgorithm::Myers);
+        assert_eq!(options.whitespace, WhitespaceMode::None);
+        assert!(!options.ignore_blank_lines);
+        assert!(!options.ignore_cr_at_eol);
+        assert_eq!(options.context, ContextSize::Lines(5));
+        assert!(!options.merge_base);
+    }
+
+    #[test]
+    fn diff_options_parse_values() {
+        let options = DiffOptions::from_query(
+            &params(&[
+                ("algorithm", "histogram"),
+                ("whitespace", "ignore-all"),
+                ("ignore_blank_lines", "true"),
+                ("ignore_cr_at_eol", "true"),
+                ("context", "full"),
+                ("merge_base", "true"),
+            ]),
+            &state(ContextSize::default(), &[]),
+        )
+        .unwrap();
+        assert_eq!(options.algorithm, DiffAlgorithm::Histogram);
+        assert_eq!(options.whitespace, WhitespaceMode::IgnoreAll);
+        assert!(options.ignore_blank_lines);
+        assert!(options.ignore_cr_at_eol);
+        assert_eq!(options.context, ContextSize::Full);
+        assert!(options.merge_base);
+    }
+
+    #[test]
+    fn diff_options_reject_invalid_value() {
+        let result = DiffOptions::from_query(
+            &params(&[("whitespace", "tabs")]),
+            &state(ContextSize::default(), &[]),
+        );
+        assert!(result.is_err());
+
+        let result = DiffOptions::from_query(
+            &params(&[("context", "-1")]),
+            &state(ContextSize::default(), &[]),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn ignore_matching_query_adds_to_config_patterns() {
+        let old = vec!["// generated at 2024".to_string(), "a".to_string()];
+        let new = vec!["// generated at 2025".to_string(), "b".to_string()];
+        let state = state(ContextSize::default(), &["^// generated at"]);
+
+        let options =
+            DiffOptions::from_query(&params(&[("ignore_matching", "^[ab]$")]), &state).unwrap();
+        let diff = options.apply(Diff::new(old, new));
+        assert!(diff.lines_structured().is_empty());
+
+        let result = DiffOptions::from_query(&params(&[("ignore_matching", "(")]), &state);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn text_diff_response_includes_encoding_and_eol() {
+        let mut git = MockGitProvider::new();
+        git.expect_blob_info().returning(|_, _, _| {
+            Ok(BlobInfo {
+                oid: "0".repeat(40),
+                size: 4,
+                binary: false,
+            })
+        });
+        git.expect_extract_text().returning(|branch, _, _| {
+            let (text, encoding, eol) = match branch {
+                "main" => ("a\r\nb\r\n", "Shift_JIS", LineEnding::Crlf),
+                _ => ("a\nc\n", "UTF-8", LineEnding::Lf),
+            };
+            text_in(text, encoding, eol)
+        });
+        let state = WebServerState {
+            git: Arc::new(git),
+            ..state(ContextSize::default(), &[])
+        };
+
+        let Ok(FileDiff::Text(diff, text)) = load_diff(&state, &params(&[("file", "a.txt")]))
+        else {
+            panic!("テキストの差分になるはず");
+        };
+        let json = serde_json::to_value(TextResponse {
+            text,
+            diff: diff.hunks(),
+        })
+        .unwrap();
+
+        assert_eq!(json["text"]["old"]["encoding"], "Shift_JIS");
+        assert_eq!(json["text"]["old"]["eol"], "crlf");
+        assert_eq!(json["text"]["new"]["eol"], "lf");
+        assert_eq!(json["diff"][0]["lines"][0]["old"]["content"], "a\r");
+    }
 }
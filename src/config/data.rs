@@ -1,4 +1,6 @@
 use crate::config::error::ConfigError;
+use crate::diff::ContextSize;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
@@ -7,6 +9,14 @@ pub struct ConfigData {
     current_branch: Option<String>,
     old_file_path: Option<String>,
     new_file_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    context: Option<ContextSize>,
+    /// パスの glob → 文字コード名。先に書いたものほど優先する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encodings: Option<IndexMap<String, String>>,
+    /// 両側が一致すれば同じ行として扱う正規表現 (git diff -I)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ignore_patterns: Option<Vec<String>>,
 }
 
 pub struct ValidatedConfigData {
@@ -14,6 +24,8 @@ pub struct ValidatedConfigData {
     current_branch: String,
     old_file_path: String,
     new_file_path: String,
+    context: ContextSize,
+    ignore_patterns: Vec<String>,
 }
 
 impl ValidatedConfigData {
@@ -32,6 +44,14 @@ impl ValidatedConfigData {
     pub fn new_file_path(&self) -> &String {
         &self.new_file_path
     }
+
+    pub fn context(&self) -> ContextSize {
+        self.context
+    }
+
+    pub fn ignore_patterns(&self) -> &[String] {
+        &self.ignore_patterns
+    }
 }
 
 pub struct ConfigScopeInput {
@@ -57,6 +77,25 @@ impl ConfigData {
         self.new_file_path.clone()
     }
 
+    /// 未設定の場合は既定の文脈行数を返す
+    pub fn context(&self) -> ContextSize {
+        self.context.unwrap_or_default()
+    }
+
+    pub fn set_context(&mut self, context: ContextSize) {
+        self.context = Some(context);
+    }
+
+    /// 未設定の場合は空で、すべてのファイルの文字コードを自動判定する
+    pub fn encodings(&self) -> IndexMap<String, String> {
+        self.encodings.clone().unwrap_or_default()
+    }
+
+    /// 未設定の場合は空で、どの行も無視しない
+    pub fn ignore_patterns(&self) -> Vec<String> {
+        self.ignore_patterns.clone().unwrap_or_default()
+    }
+
     pub fn set_base_branch(&mut self, branch: String) -> Result<(), ConfigError> {
         if branch.is_empty() {
             return Err(ConfigError::EmptyBranchName);
@@ -90,6 +129,8 @@ impl TryFrom<ConfigData> for ValidatedConfigData {
             .ok_or(ConfigError::MissingField("new_file_path".to_string()))?;
 
         Ok(ValidatedConfigData {
+            context: config.context.unwrap_or_default(),
+            ignore_patterns: config.ignore_patterns.unwrap_or_default(),
             base_branch,
             current_branch,
             old_file_path,
@@ -112,8 +153,45 @@ mod tests {
             current_branch: None,
             old_file_path: None,
             new_file_path: None,
+            context: None,
+            encodings: None,
+            ignore_patterns: None,
         };
         assert_eq!(config.base_branch(), Some("main".to_string()));
+        assert_eq!(config.context(), ContextSize::Lines(3));
+    }
+
+    #[test]
+    fn test_config_data_set_context() {
+        let mut config = ConfigData::default();
+        config.set_context(ContextSize::Full);
+        assert_eq!(config.context(), ContextSize::Full);
+
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        let loaded: ConfigData = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(loaded, config);
+    }
+
+    #[test]
+    fn test_config_data_encodings_keep_order() {
+        let yaml = "encodings:\n  legacy/**: shift_jis\n  '**/*.txt': euc-jp\n";
+        let config: ConfigData = serde_yaml::from_str(yaml).unwrap();
+        let globs: Vec<_> = config.encodings().into_keys().collect();
+        assert_eq!(globs, vec!["legacy/**", "**/*.txt"]);
+
+        assert!(ConfigData::default().encodings().is_empty());
+    }
+
+    #[test]
+    fn test_config_data_ignore_patterns() {
+        let yaml = "ignore_patterns:\n  - '^// generated at'\n  - 'version = \"[0-9.]+\"'\n";
+        let config: ConfigData = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            config.ignore_patterns(),
+            vec!["^// generated at", "version = \"[0-9.]+\""]
+        );
+
+        assert!(ConfigData::default().ignore_patterns().is_empty());
     }
 
     #[test]
@@ -43,10 +43,10 @@ impl<S: Serializer, F: FileSystem> ConfigStorage<S, F> {
     }
 
     fn ensure_directory_exists(&self) -> Result<(), ConfigError> {
-        if let Some(dir) = self.file_path.parent() {
-            if !self.fs.exists(dir) {
-                self.fs.create_dir_all(dir)?;
-            }
+        if let Some(dir) = self.file_path.parent()
+            && !self.fs.exists(dir)
+        {
+            self.fs.create_dir_all(dir)?;
         }
         Ok(())
     }
@@ -271,12 +271,7 @@ mod tests {
                     mockall::predicate::eq(config_path.clone()),
                     mockall::predicate::eq("base_branch: null\n"),
                 )
-                .returning(|_, _| {
-                    Err(std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        "File system error",
-                    ))
-                });
+                .returning(|_, _| Err(std::io::Error::other("File system error")));
 
             let storage =
                 ConfigStorage::new(config_path, Arc::new(mock_fs), Arc::new(mock_serializer))
@@ -359,12 +354,7 @@ mod tests {
             mock_fs
                 .expect_read_to_string()
                 .with(mockall::predicate::eq(config_path.clone()))
-                .returning(|_| {
-                    Err(std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        "File system error",
-                    ))
-                });
+                .returning(|_| Err(std::io::Error::other("File system error")));
 
             mock_serializer.expect_deserialize::<ConfigData>().times(0);
 
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::{lcs_matches, trim_common};
+
+/// これを超えて出現する行は軸の候補にしない（git と同じ上限）
+const MAX_CHAIN_LEN: usize = 64;
+
+struct Region {
+    x_start: usize,
+    y_start: usize,
+    len: usize,
+    count: usize,
+}
+
+/// 出現回数が最も少ない行を含む共通区間を軸にして再帰的に揃える
+pub fn matches<T: Eq + Hash>(x: &[T], y: &[T]) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    let mut stack = vec![((0, x.len()), (0, y.len()))];
+
+    while let Some((x_range, y_range)) = stack.pop() {
+        let ((x_lo, x_hi), (y_lo, y_hi)) = trim_common(x, y, x_range, y_range, &mut matches);
+        if x_lo == x_hi || y_lo == y_hi {
+            continue;
+        }
+
+        let mut occurrences: HashMap<&T, Vec<usize>> = HashMap::new();
+        for (i, line) in x.iter().enumerate().take(x_hi).skip(x_lo) {
+            occurrences.entry(line).or_default().push(i);
+        }
+
+        let mut best: Option<Region> = None;
+        let mut too_common = false;
+        let mut j = y_lo;
+
+        while j < y_hi {
+            let Some(positions) = occurrences.get(&y[j]) else {
+                j += 1;
+                continue;
+            };
+            if positions.len() > MAX_CHAIN_LEN {
+                too_common = true;
+                j += 1;
+                continue;
+            }
+
+            let mut next_j = j + 1;
+            for &i in positions {
+                let (mut x_start, mut y_start) = (i, j);
+                while x_start > x_lo && y_start > y_lo && x[x_start - 1] == y[y_start - 1] {
+                    x_start -= 1;
+                    y_start -= 1;
+                }
+                let (mut x_end, mut y_end) = (i + 1, j + 1);
+                while x_end < x_hi && y_end < y_hi && x[x_end] == y[y_end] {
+                    x_end += 1;
+                    y_end += 1;
+                }
+
+                let count = x[x_start..x_end]
+                    .iter()
+                    .map(|line| occurrences[line].len())
+                    .min()
+                    .unwrap_or(usize::MAX);
+                let len = x_end - x_start;
+
+                let is_better = best
+                    .as_ref()
+                    .is_none_or(|b| count < b.count || (count == b.count && len > b.len));
+                if is_better {
+                    best = Some(Region {
+                        x_start,
+                        y_start,
+                        len,
+                        count,
+                    });
+                }
+                next_j = next_j.max(y_end);
+            }
+            j = next_j;
+        }
+
+        match best {
+            Some(region) => {
+                for k in 0..region.len {
+                    matches.push((region.x_start + k, region.y_start + k));
+                }
+                stack.push(((x_lo, region.x_start), (y_lo, region.y_start)));
+                stack.push((
+                    (region.x_start + region.len, x_hi),
+                    (region.y_start + region.len, y_hi),
+                ));
+            }
+            None if too_common => {
+                lcs_matches(x, y, (x_lo, x_hi), (y_lo, y_hi), &mut matches);
+            }
+            None => {}
+        }
+    }
+
+    matches.sort_unstable();
+    matches
+}
@@ -0,0 +1,271 @@
+mod histogram;
+mod myers;
+mod patience;
+
+use std::fmt;
+use std::hash::Hash;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use prettydiff::basic::{self, DiffOp};
+
+#[derive(ValueEnum, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffAlgorithm {
+    /// 最小差分。小さな入力では従来どおり prettydiff の LCS を使う
+    #[default]
+    Myers,
+    /// 両側で一度だけ出現する行を軸に揃える
+    Patience,
+    /// 出現回数の少ない行を優先して軸にする（git の histogram 相当）
+    Histogram,
+}
+
+impl DiffAlgorithm {
+    pub fn diff<'a, T: Eq + Hash>(&self, x: &'a [T], y: &'a [T]) -> Vec<DiffOp<'a, T>> {
+        match self {
+            DiffAlgorithm::Myers if fits_lcs_table(x.len(), y.len()) => basic::diff(x, y),
+            DiffAlgorithm::Myers => ops_from_matches(x, y, &myers::matches(x, y)),
+            DiffAlgorithm::Patience => ops_from_matches(x, y, &patience::matches(x, y)),
+            DiffAlgorithm::Histogram => ops_from_matches(x, y, &histogram::matches(x, y)),
+        }
+    }
+}
+
+impl fmt::Display for DiffAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            DiffAlgorithm::Myers => "myers",
+            DiffAlgorithm::Patience => "patience",
+            DiffAlgorithm::Histogram => "histogram",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+impl FromStr for DiffAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true)
+            .map_err(|_| format!("不明な差分アルゴリズムです: {}", s))
+    }
+}
+
+/// prettydiff の LCS は行数の積に比例したメモリを使うため、これを超える場合は Myers 法に切り替える
+const MAX_LCS_TABLE_CELLS: usize = 1 << 22;
+
+fn fits_lcs_table(x_len: usize, y_len: usize) -> bool {
+    x_len.saturating_mul(y_len) <= MAX_LCS_TABLE_CELLS
+}
+
+/// `[lo, hi)` の範囲同士で、先頭と末尾の一致行を取り除いた範囲を返す
+fn trim_common<T: PartialEq>(
+    x: &[T],
+    y: &[T],
+    (mut x_lo, mut x_hi): (usize, usize),
+    (mut y_lo, mut y_hi): (usize, usize),
+    matches: &mut Vec<(usize, usize)>,
+) -> ((usize, usize), (usize, usize)) {
+    while x_lo < x_hi && y_lo < y_hi && x[x_lo] == y[y_lo] {
+        matches.push((x_lo, y_lo));
+        x_lo += 1;
+        y_lo += 1;
+    }
+    while x_lo < x_hi && y_lo < y_hi && x[x_hi - 1] == y[y_hi - 1] {
+        x_hi -= 1;
+        y_hi -= 1;
+        matches.push((x_hi, y_hi));
+    }
+    ((x_lo, x_hi), (y_lo, y_hi))
+}
+
+/// 軸が見つからない範囲は LCS で埋める
+fn lcs_matches<T: PartialEq>(
+    x: &[T],
+    y: &[T],
+    (x_lo, x_hi): (usize, usize),
+    (y_lo, y_hi): (usize, usize),
+    matches: &mut Vec<(usize, usize)>,
+) {
+    if !fits_lcs_table(x_hi - x_lo, y_hi - y_lo) {
+        let found = myers::matches(&x[x_lo..x_hi], &y[y_lo..y_hi]);
+        matches.extend(found.into_iter().map(|(i, j)| (x_lo + i, y_lo + j)));
+        return;
+    }
+
+    let mut i = x_lo;
+    let mut j = y_lo;
+    for op in basic::diff(&x[x_lo..x_hi], &y[y_lo..y_hi]) {
+        match op {
+            DiffOp::Equal(a) => {
+                for k in 0..a.len() {
+                    matches.push((i + k, j + k));
+                }
+                i += a.len();
+                j += a.len();
+            }
+            DiffOp::Insert(b) => j += b.len(),
+            DiffOp::Remove(a) => i += a.len(),
+            DiffOp::Replace(a, b) => {
+                i += a.len();
+                j += b.len();
+            }
+        }
+    }
+}
+
+/// 一致した行の組 (昇順) から prettydiff と同じ形の DiffOp 列を組み立てる
+fn ops_from_matches<'a, T>(
+    x: &'a [T],
+    y: &'a [T],
+    matches: &[(usize, usize)],
+) -> Vec<DiffOp<'a, T>> {
+    let mut ops = Vec::new();
+    let mut i = 0;
+    let mut j = 0;
+    let mut k = 0;
+
+    loop {
+        let (m_x, m_y) = matches.get(k).copied().unwrap_or((x.len(), y.len()));
+
+        if i < m_x && j < m_y {
+            ops.push(DiffOp::Replace(&x[i..m_x], &y[j..m_y]));
+        } else if i < m_x {
+            ops.push(DiffOp::Remove(&x[i..m_x]));
+        } else if j < m_y {
+            ops.push(DiffOp::Insert(&y[j..m_y]));
+        }
+
+        if k >= matches.len() {
+            break;
+        }
+
+        let mut len = 1;
+        while matches
+            .get(k + len)
+            .is_some_and(|&(a, b)| a == m_x + len && b == m_y + len)
+        {
+            len += 1;
+        }
+
+        ops.push(DiffOp::Equal(&x[m_x..m_x + len]));
+        i = m_x + len;
+        j = m_y + len;
+        k += len;
+    }
+
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn equal_lines<'a>(ops: &[DiffOp<'a, &'a str>]) -> Vec<&'a str> {
+        ops.iter()
+            .filter_map(|op| match op {
+                DiffOp::Equal(a) => Some(a.to_vec()),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    fn assert_consistent(algorithm: DiffAlgorithm, x: &[&str], y: &[&str]) {
+        let ops = algorithm.diff(x, y);
+        let mut old = Vec::new();
+        let mut new = Vec::new();
+        for op in &ops {
+            match op {
+                DiffOp::Equal(a) => {
+                    old.extend_from_slice(a);
+                    new.extend_from_slice(a);
+                }
+                DiffOp::Insert(b) => new.extend_from_slice(b),
+                DiffOp::Remove(a) => old.extend_from_slice(a),
+                DiffOp::Replace(a, b) => {
+                    old.extend_from_slice(a);
+                    new.extend_from_slice(b);
+                }
+            }
+        }
+        assert_eq!(old, x);
+        assert_eq!(new, y);
+    }
+
+    const MOVED_OLD: [&str; 9] = [
+        "fn a() {",
+        "    one();",
+        "}",
+        "",
+        "fn b() {",
+        "    two();",
+        "}",
+        "",
+        "fn c() {}",
+    ];
+    const MOVED_NEW: [&str; 9] = [
+        "fn b() {",
+        "    two();",
+        "}",
+        "",
+        "fn a() {",
+        "    one();",
+        "}",
+        "",
+        "fn c() {}",
+    ];
+
+    #[test]
+    fn all_algorithms_reconstruct_both_sides() {
+        let inputs: [(&[&str], &[&str]); 4] = [
+            (&[], &[]),
+            (&["a", "b", "c"], &[]),
+            (
+                &["q", "a", "b", "x", "c", "d"],
+                &["a", "b", "y", "c", "d", "f"],
+            ),
+            (&MOVED_OLD, &MOVED_NEW),
+        ];
+
+        for algorithm in DiffAlgorithm::value_variants() {
+            for (x, y) in inputs {
+                assert_consistent(*algorithm, x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn myers_matches_prettydiff() {
+        let x = ["q", "a", "b", "x", "c", "d"];
+        let y = ["a", "b", "y", "c", "d", "f"];
+        assert_eq!(DiffAlgorithm::Myers.diff(&x, &y), basic::diff(&x, &y));
+    }
+
+    #[test]
+    fn patience_anchors_on_unique_lines() {
+        let ops = DiffAlgorithm::Patience.diff(&MOVED_OLD, &MOVED_NEW);
+        assert_eq!(
+            equal_lines(&ops),
+            vec!["fn b() {", "    two();", "}", "", "fn c() {}"]
+        );
+    }
+
+    #[test]
+    fn histogram_extends_region_around_rare_line() {
+        let x = ["x", "}", "foo", "}"];
+        let y = ["}", "foo", "}", "y"];
+        let ops = DiffAlgorithm::Histogram.diff(&x, &y);
+        assert_eq!(equal_lines(&ops), vec!["}", "foo", "}"]);
+    }
+
+    #[test]
+    fn from_str_ignores_case() {
+        assert_eq!(
+            "Patience".parse::<DiffAlgorithm>(),
+            Ok(DiffAlgorithm::Patience)
+        );
+        assert!("unknown".parse::<DiffAlgorithm>().is_err());
+    }
+}
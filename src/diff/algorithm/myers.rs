@@ -0,0 +1,206 @@
+use std::ops::{Index, IndexMut, Range};
+
+/// Myers 法 (線形メモリ版) で一致する行の組を昇順で返す。
+/// LCS の表を作らないため、行数が多くても O(N + M) のメモリで済む
+pub fn matches<T: PartialEq>(x: &[T], y: &[T]) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    let max_d = max_d(x.len(), y.len());
+    let mut vf = V::new(max_d);
+    let mut vb = V::new(max_d);
+    conquer(x, 0..x.len(), y, 0..y.len(), &mut vf, &mut vb, &mut matches);
+    matches
+}
+
+fn max_d(x_len: usize, y_len: usize) -> usize {
+    (x_len + y_len).div_ceil(2) + 1
+}
+
+/// 負の対角線番号でも引けるようにした V 配列
+struct V {
+    offset: isize,
+    v: Vec<usize>,
+}
+
+impl V {
+    fn new(max_d: usize) -> Self {
+        Self {
+            offset: max_d as isize,
+            v: vec![0; 2 * max_d],
+        }
+    }
+}
+
+impl Index<isize> for V {
+    type Output = usize;
+
+    fn index(&self, index: isize) -> &usize {
+        &self.v[(index + self.offset) as usize]
+    }
+}
+
+impl IndexMut<isize> for V {
+    fn index_mut(&mut self, index: isize) -> &mut usize {
+        &mut self.v[(index + self.offset) as usize]
+    }
+}
+
+fn conquer<T: PartialEq>(
+    x: &[T],
+    mut x_range: Range<usize>,
+    y: &[T],
+    mut y_range: Range<usize>,
+    vf: &mut V,
+    vb: &mut V,
+    matches: &mut Vec<(usize, usize)>,
+) {
+    while !x_range.is_empty() && !y_range.is_empty() && x[x_range.start] == y[y_range.start] {
+        matches.push((x_range.start, y_range.start));
+        x_range.start += 1;
+        y_range.start += 1;
+    }
+    let mut suffix = 0;
+    while !x_range.is_empty() && !y_range.is_empty() && x[x_range.end - 1] == y[y_range.end - 1] {
+        x_range.end -= 1;
+        y_range.end -= 1;
+        suffix += 1;
+    }
+
+    if !x_range.is_empty() && !y_range.is_empty() {
+        let (x_mid, y_mid) = middle_snake(x, x_range.clone(), y, y_range.clone(), vf, vb);
+        conquer(
+            x,
+            x_range.start..x_mid,
+            y,
+            y_range.start..y_mid,
+            vf,
+            vb,
+            matches,
+        );
+        conquer(
+            x,
+            x_mid..x_range.end,
+            y,
+            y_mid..y_range.end,
+            vf,
+            vb,
+            matches,
+        );
+    }
+
+    matches.extend((0..suffix).map(|k| (x_range.end + k, y_range.end + k)));
+}
+
+/// 前方と後方から同時に探索し、最短編集経路の中央にある snake の始点を返す
+fn middle_snake<T: PartialEq>(
+    x: &[T],
+    x_range: Range<usize>,
+    y: &[T],
+    y_range: Range<usize>,
+    vf: &mut V,
+    vb: &mut V,
+) -> (usize, usize) {
+    let n = x_range.len();
+    let m = y_range.len();
+    let delta = n as isize - m as isize;
+    let odd = delta & 1 == 1;
+    vf[1] = 0;
+    vb[1] = 0;
+
+    for d in 0..max_d(n, m) as isize {
+        for k in (-d..=d).rev().step_by(2) {
+            let mut i = if k == -d || (k != d && vf[k - 1] < vf[k + 1]) {
+                vf[k + 1]
+            } else {
+                vf[k - 1] + 1
+            };
+            let mut j = (i as isize - k) as usize;
+            let (i0, j0) = (i, j);
+            while i < n && j < m && x[x_range.start + i] == y[y_range.start + j] {
+                i += 1;
+                j += 1;
+            }
+            vf[k] = i;
+            if odd && (k - delta).abs() < d && vf[k] + vb[-(k - delta)] >= n {
+                return (x_range.start + i0, y_range.start + j0);
+            }
+        }
+
+        for k in (-d..=d).rev().step_by(2) {
+            let mut i = if k == -d || (k != d && vb[k - 1] < vb[k + 1]) {
+                vb[k + 1]
+            } else {
+                vb[k - 1] + 1
+            };
+            let mut j = (i as isize - k) as usize;
+            while i < n && j < m && x[x_range.end - i - 1] == y[y_range.end - j - 1] {
+                i += 1;
+                j += 1;
+            }
+            vb[k] = i;
+            if !odd && (k - delta).abs() <= d && vb[k] + vf[-(k - delta)] >= n {
+                return (x_range.end - i, y_range.end - j);
+            }
+        }
+    }
+
+    // 編集距離の上限までに必ず見つかるため、ここには到達しない
+    (x_range.end, y_range.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prettydiff::basic;
+
+    fn lcs_len(x: &[char], y: &[char]) -> usize {
+        basic::diff(x, y)
+            .iter()
+            .map(|op| match op {
+                basic::DiffOp::Equal(a) => a.len(),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    #[test]
+    fn finds_a_longest_common_subsequence() {
+        let cases = [
+            ("abcabba", "cbabac"),
+            ("", "abc"),
+            ("abc", ""),
+            ("abcdef", "abcdef"),
+            ("xaxbxcx", "abc"),
+            ("the quick brown fox", "the quack brown box"),
+        ];
+
+        for (x, y) in cases {
+            let x: Vec<char> = x.chars().collect();
+            let y: Vec<char> = y.chars().collect();
+            let found = matches(&x, &y);
+
+            assert_eq!(found.len(), lcs_len(&x, &y), "{x:?} / {y:?}");
+            assert!(found.iter().all(|&(i, j)| x[i] == y[j]));
+            assert!(found.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1));
+        }
+    }
+
+    #[test]
+    fn agrees_with_lcs_on_generated_inputs() {
+        let mut seed: u32 = 7;
+        let mut next = |modulo: u32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) % modulo
+        };
+
+        for _ in 0..200 {
+            let x: Vec<char> = (0..next(30))
+                .map(|_| (b'a' + next(4) as u8) as char)
+                .collect();
+            let y: Vec<char> = (0..next(30))
+                .map(|_| (b'a' + next(4) as u8) as char)
+                .collect();
+
+            assert_eq!(matches(&x, &y).len(), lcs_len(&x, &y), "{x:?} / {y:?}");
+        }
+    }
+}
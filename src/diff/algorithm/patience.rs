@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::{lcs_matches, trim_common};
+
+/// 両側でちょうど一度ずつ現れる行を軸にして再帰的に揃える
+pub fn matches<T: Eq + Hash>(x: &[T], y: &[T]) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    let mut stack = vec![((0, x.len()), (0, y.len()))];
+
+    while let Some((x_range, y_range)) = stack.pop() {
+        let ((x_lo, x_hi), (y_lo, y_hi)) = trim_common(x, y, x_range, y_range, &mut matches);
+        if x_lo == x_hi || y_lo == y_hi {
+            continue;
+        }
+
+        let anchors = unique_anchors(x, y, (x_lo, x_hi), (y_lo, y_hi));
+        if anchors.is_empty() {
+            lcs_matches(x, y, (x_lo, x_hi), (y_lo, y_hi), &mut matches);
+            continue;
+        }
+
+        let mut prev_x = x_lo;
+        let mut prev_y = y_lo;
+        for (a_x, a_y) in anchors {
+            matches.push((a_x, a_y));
+            stack.push(((prev_x, a_x), (prev_y, a_y)));
+            prev_x = a_x + 1;
+            prev_y = a_y + 1;
+        }
+        stack.push(((prev_x, x_hi), (prev_y, y_hi)));
+    }
+
+    matches.sort_unstable();
+    matches
+}
+
+fn unique_anchors<T: Eq + Hash>(
+    x: &[T],
+    y: &[T],
+    (x_lo, x_hi): (usize, usize),
+    (y_lo, y_hi): (usize, usize),
+) -> Vec<(usize, usize)> {
+    // 行ごとに (x での出現数, x での位置, y での出現数, y での位置)
+    let mut table: HashMap<&T, (usize, usize, usize, usize)> = HashMap::new();
+    for (i, line) in x.iter().enumerate().take(x_hi).skip(x_lo) {
+        let entry = table.entry(line).or_insert((0, i, 0, 0));
+        entry.0 += 1;
+    }
+    for (j, line) in y.iter().enumerate().take(y_hi).skip(y_lo) {
+        if let Some(entry) = table.get_mut(line) {
+            entry.2 += 1;
+            entry.3 = j;
+        }
+    }
+
+    let mut pairs: Vec<(usize, usize)> = table
+        .into_values()
+        .filter(|&(x_count, _, y_count, _)| x_count == 1 && y_count == 1)
+        .map(|(_, i, _, j)| (i, j))
+        .collect();
+    pairs.sort_unstable();
+
+    longest_increasing(&pairs)
+}
+
+/// x 順に並んだ組から y も単調増加となる最長の部分列を選ぶ
+fn longest_increasing(pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut tails: Vec<usize> = Vec::new();
+    let mut prev: Vec<Option<usize>> = vec![None; pairs.len()];
+
+    for (k, &(_, j)) in pairs.iter().enumerate() {
+        let pos = tails.partition_point(|&t| pairs[t].1 < j);
+        if pos > 0 {
+            prev[k] = Some(tails[pos - 1]);
+        }
+        if pos == tails.len() {
+            tails.push(k);
+        } else {
+            tails[pos] = k;
+        }
+    }
+
+    let mut result = Vec::with_capacity(tails.len());
+    let mut cursor = tails.last().copied();
+    while let Some(k) = cursor {
+        result.push(pairs[k]);
+        cursor = prev[k];
+    }
+    result.reverse();
+    result
+}
@@ -0,0 +1,290 @@
+//! 大きなファイルでの計測。通常のテストでは実行せず、下記で結果を表示する
+//! `cargo test --release benches -- --ignored --nocapture`
+//!
+//! 比べる相手は、共有ストレージと文脈の絞り込みを入れる前の実装の写し ([`baseline`])。
+//! メモリはスレッドごとに数えるアロケータで、計測中の確保の最大量と回数を測る
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use prettydiff::basic::DiffOp;
+
+use super::{Diff, DiffProvider, DiffType, LineDiff, LineInfo, LineOp};
+
+const LINES: usize = 100_000;
+
+thread_local! {
+    /// このスレッドで確保中のバイト数と、計測開始からの最大値・確保の回数
+    static CURRENT: Cell<isize> = const { Cell::new(0) };
+    static PEAK: Cell<isize> = const { Cell::new(0) };
+    static COUNT: Cell<usize> = const { Cell::new(0) };
+}
+
+struct CountingAllocator;
+
+fn track(delta: isize, allocation: bool) {
+    let _ = CURRENT.try_with(|current| {
+        let now = current.get() + delta;
+        current.set(now);
+        let _ = PEAK.try_with(|peak| peak.set(peak.get().max(now)));
+    });
+    if allocation {
+        let _ = COUNT.try_with(|count| count.set(count.get() + 1));
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        track(layout.size() as isize, true);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        track(-(layout.size() as isize), false);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        track(new_size as isize - layout.size() as isize, true);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// 計測結果。`peak` は計測開始時点から増えた確保量の最大値
+struct Measurement {
+    time: Duration,
+    peak: usize,
+    allocations: usize,
+}
+
+impl std::fmt::Display for Measurement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:>9.1?} / 最大 {:>7.1} MiB / 確保 {:>8} 回",
+            self.time,
+            self.peak as f64 / (1024.0 * 1024.0),
+            self.allocations
+        )
+    }
+}
+
+fn measure<T>(f: impl FnOnce() -> T) -> (T, Measurement) {
+    let start_bytes = CURRENT.with(Cell::get);
+    PEAK.with(|peak| peak.set(start_bytes));
+    let start_count = COUNT.with(Cell::get);
+    let start = Instant::now();
+
+    let value = f();
+
+    let time = start.elapsed();
+    let measurement = Measurement {
+        time,
+        peak: (PEAK.with(Cell::get) - start_bytes).max(0) as usize,
+        allocations: COUNT.with(Cell::get) - start_count,
+    };
+    (value, measurement)
+}
+
+/// 1000 行ごとに 1 行だけ書き換えた、生成コード風のファイル
+fn generated_file(lines: usize) -> (Vec<String>, Vec<String>) {
+    let old: Vec<String> = (0..lines)
+        .map(|i| format!("    pub const ENTRY_{i:06}: u64 = {};", i * 31))
+        .collect();
+    let mut new = old.clone();
+    for i in (500..lines).step_by(1000) {
+        new[i] = format!("    pub const ENTRY_{i:06}: u64 = {};", i * 37);
+    }
+    (old, new)
+}
+
+/// 以前の `Diff::lines_structured_with_context` の写し。`Vec<String>` を受け取って複製を持ち、
+/// `Vec<&str>` を作り直したうえで、全行の内容を複製した `raw` を作ってから文脈行で絞り込む
+mod baseline {
+    use super::*;
+
+    /// 以前は行の対応付けに prettydiff の LCS を使っていたが、10 万行では行数の 2 乗の表が作れない。
+    /// そのため対応付けには現在の実装の結果を渡し、それ以外を以前と同じ手順で行う
+    pub fn lines_structured_with_context(
+        old: Vec<String>,
+        new: Vec<String>,
+        ops: &[LineOp],
+        context: usize,
+    ) -> Vec<LineDiff> {
+        let old_refs: Vec<&str> = old.iter().map(|s| s.as_str()).collect();
+        let new_refs: Vec<&str> = new.iter().map(|s| s.as_str()).collect();
+        let diff = ops
+            .iter()
+            .map(|op| match op {
+                LineOp::Equal(old, _) => DiffOp::Equal(&old_refs[old.clone()]),
+                LineOp::Insert(new) => DiffOp::Insert(&new_refs[new.clone()]),
+                LineOp::Remove(old) => DiffOp::Remove(&old_refs[old.clone()]),
+                LineOp::Replace(old, new) => {
+                    DiffOp::Replace(&old_refs[old.clone()], &new_refs[new.clone()])
+                }
+            })
+            .collect();
+        filter_context(diff, context)
+    }
+
+    fn line(lineno: usize, content: &str, diff_type: DiffType) -> LineInfo {
+        LineInfo {
+            lineno,
+            content: content.to_string(),
+            diff_type,
+            spans: None,
+            move_id: None,
+        }
+    }
+
+    fn filter_context(changeset: Vec<DiffOp<&str>>, context: usize) -> Vec<LineDiff> {
+        let mut raw = Vec::new();
+        let mut old_lineno = 1;
+        let mut new_lineno = 1;
+
+        for change in changeset {
+            match change {
+                DiffOp::Insert(lines) => {
+                    for content in lines {
+                        let new = line(new_lineno, content, DiffType::Added);
+                        raw.push((
+                            LineDiff {
+                                old: None,
+                                new: Some(new),
+                            },
+                            true,
+                        ));
+                        new_lineno += 1;
+                    }
+                }
+                DiffOp::Remove(lines) => {
+                    for content in lines {
+                        let old = line(old_lineno, content, DiffType::Removed);
+                        raw.push((
+                            LineDiff {
+                                old: Some(old),
+                                new: None,
+                            },
+                            true,
+                        ));
+                        old_lineno += 1;
+                    }
+                }
+                DiffOp::Equal(lines) => {
+                    for content in lines {
+                        raw.push((
+                            LineDiff {
+                                old: Some(line(old_lineno, content, DiffType::Equal)),
+                                new: Some(line(new_lineno, content, DiffType::Equal)),
+                            },
+                            false,
+                        ));
+                        old_lineno += 1;
+                        new_lineno += 1;
+                    }
+                }
+                DiffOp::Replace(old_lines, new_lines) => {
+                    let count = old_lines.len().max(new_lines.len());
+                    for i in 0..count {
+                        let old = old_lines.get(i);
+                        let new = new_lines.get(i);
+                        let diff_type = match (old, new) {
+                            (Some(_), Some(_)) => DiffType::Replaced,
+                            (Some(_), None) => DiffType::Removed,
+                            (None, Some(_)) => DiffType::Added,
+                            (None, None) => continue,
+                        };
+                        raw.push((
+                            LineDiff {
+                                old: old.map(|content| line(old_lineno + i, content, diff_type)),
+                                new: new.map(|content| line(new_lineno + i, content, diff_type)),
+                            },
+                            true,
+                        ));
+                    }
+                    old_lineno += old_lines.len();
+                    new_lineno += new_lines.len();
+                }
+            }
+        }
+
+        let mut needed = vec![false; raw.len()];
+        for (i, (_, is_change)) in raw.iter().enumerate() {
+            if *is_change {
+                let start = i.saturating_sub(context);
+                let end = (i + context + 1).min(raw.len());
+                needed
+                    .iter_mut()
+                    .take(end)
+                    .skip(start)
+                    .for_each(|flag| *flag = true);
+            }
+        }
+
+        raw.into_iter()
+            .enumerate()
+            .filter_map(|(i, (line, _))| needed[i].then_some(line))
+            .collect()
+    }
+}
+
+/// 以前の実装の計測には対応付けの時間と確保を含めないため、現在の実装に不利な比較になる
+#[test]
+#[ignore]
+fn benches_context_filtering_on_100k_lines() {
+    let (old, new) = generated_file(LINES);
+    let ops = Diff::new(old.clone(), new.clone()).line_ops();
+    let shared: (Arc<[String]>, Arc<[String]>) = (old.clone().into(), new.clone().into());
+
+    let (before, before_stats) =
+        measure(|| baseline::lines_structured_with_context(old.clone(), new.clone(), &ops, 3));
+    let (after, after_stats) =
+        measure(|| Diff::new(shared.0.clone(), shared.1.clone()).lines_structured());
+
+    println!("{LINES} 行 / 変更 {} 箇所", LINES / 1000);
+    println!("以前の実装: {before_stats} / {:>4} 行", before.len());
+    println!("現在の実装: {after_stats} / {:>4} 行", after.len());
+
+    assert_eq!(before.len(), after.len());
+    assert!(after_stats.peak * 3 < before_stats.peak);
+    assert!(after_stats.allocations * 10 < before_stats.allocations);
+}
+
+/// 文脈行数を変えて同じファイルを 5 回比較する。以前は比較のたびに行を複製していた
+#[test]
+#[ignore]
+fn benches_shared_storage_on_100k_lines() {
+    let (old, new) = generated_file(LINES);
+    let ops = Diff::new(old.clone(), new.clone()).line_ops();
+    let shared: (Arc<[String]>, Arc<[String]>) = (old.clone().into(), new.clone().into());
+
+    let (before, before_stats) = measure(|| {
+        (0..5)
+            .map(|context| {
+                baseline::lines_structured_with_context(old.clone(), new.clone(), &ops, context)
+                    .len()
+            })
+            .sum::<usize>()
+    });
+    let (after, after_stats) = measure(|| {
+        (0..5)
+            .map(|context| {
+                Diff::new(shared.0.clone(), shared.1.clone())
+                    .lines_structured_with_context(context)
+                    .len()
+            })
+            .sum::<usize>()
+    });
+
+    println!("{LINES} 行を 5 回比較");
+    println!("以前の実装: {before_stats} / {before:>4} 行");
+    println!("現在の実装: {after_stats} / {after:>4} 行");
+
+    assert_eq!(before, after);
+    assert!(after_stats.allocations * 10 < before_stats.allocations);
+}
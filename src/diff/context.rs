@@ -0,0 +1,97 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// 変更行の前後に残す文脈行数
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContextSize {
+    Lines(usize),
+    /// ファイル全体を表示する
+    Full,
+}
+
+impl ContextSize {
+    pub fn lines(&self) -> usize {
+        match self {
+            ContextSize::Lines(lines) => *lines,
+            ContextSize::Full => usize::MAX,
+        }
+    }
+}
+
+impl Default for ContextSize {
+    fn default() -> Self {
+        ContextSize::Lines(3)
+    }
+}
+
+impl fmt::Display for ContextSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContextSize::Lines(lines) => write!(f, "{}", lines),
+            ContextSize::Full => write!(f, "full"),
+        }
+    }
+}
+
+impl FromStr for ContextSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("full") {
+            return Ok(ContextSize::Full);
+        }
+        s.parse::<usize>().map(ContextSize::Lines).map_err(|_| {
+            format!(
+                "文脈の行数 '{}' が正しくありません。数値または full を指定してください",
+                s
+            )
+        })
+    }
+}
+
+impl Serialize for ContextSize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ContextSize::Lines(lines) => serializer.serialize_u64(*lines as u64),
+            ContextSize::Full => serializer.serialize_str("full"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ContextSize {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Lines(usize),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Lines(lines) => Ok(ContextSize::Lines(lines)),
+            Raw::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_number_and_full() {
+        assert_eq!("10".parse::<ContextSize>(), Ok(ContextSize::Lines(10)));
+        assert_eq!("FULL".parse::<ContextSize>(), Ok(ContextSize::Full));
+        assert!("-1".parse::<ContextSize>().is_err());
+    }
+
+    #[test]
+    fn round_trips_through_yaml() {
+        for context in [ContextSize::Lines(0), ContextSize::Full] {
+            let yaml = serde_yaml::to_string(&context).unwrap();
+            assert_eq!(serde_yaml::from_str::<ContextSize>(&yaml).unwrap(), context);
+        }
+    }
+}
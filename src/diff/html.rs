@@ -0,0 +1,278 @@
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::{IncludeBackground, styled_line_to_highlighted_html};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+
+use super::unified::hunk_header;
+use super::{Hunk, LineDiff, PatchTarget};
+
+static SYNTAXES: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+static THEME: Lazy<Theme> = Lazy::new(|| {
+    ThemeSet::load_defaults()
+        .themes
+        .remove("InspiredGitHub")
+        .unwrap_or_default()
+});
+
+const STYLE: &str = "
+body { margin: 0; padding: 24px; font-family: -apple-system, 'Segoe UI', 'Hiragino Sans', sans-serif; color: #1f2328; background: #f6f8fa; }
+h1 { font-size: 20px; margin: 0 0 16px; }
+nav ul { margin: 0 0 24px; padding-left: 20px; }
+nav a { color: #0969da; text-decoration: none; }
+.summary { color: #59636e; font-size: 12px; margin-left: 8px; }
+section { margin-bottom: 24px; background: #fff; border: 1px solid #d1d9e0; border-radius: 6px; overflow: hidden; }
+section h2 { font-size: 14px; margin: 0; padding: 8px 12px; background: #f6f8fa; border-bottom: 1px solid #d1d9e0; }
+.note { margin: 0; padding: 12px; color: #59636e; }
+table { width: 100%; border-collapse: collapse; font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; font-size: 12px; }
+td { padding: 0 8px; vertical-align: top; }
+td.num { width: 1%; min-width: 40px; text-align: right; color: #59636e; user-select: none; }
+td.code { white-space: pre-wrap; word-break: break-all; }
+tr.hunk td { color: #59636e; background: #ddf4ff; padding: 4px 8px; }
+tr.removed td { background: #ffebe9; }
+tr.removed td.num { background: #ffd7d5; }
+tr.added td { background: #e6ffec; }
+tr.added td.num { background: #ccffd8; }
+";
+
+/// HTML レポートに載せるファイル 1 つ分。`body` は [`Diff::html`](super::Diff::html) で作る
+pub struct HtmlFile {
+    pub path: String,
+    /// 見出しの横に出す、追加・削除行数などの要約
+    pub summary: String,
+    pub body: String,
+}
+
+impl HtmlFile {
+    /// 差分の代わりに説明だけを載せる (バイナリファイルなど)
+    pub fn note(path: String, summary: String, text: &str) -> Self {
+        Self {
+            path,
+            summary,
+            body: note(text),
+        }
+    }
+}
+
+/// CSS を埋め込んだ、サーバーなしで開ける 1 ファイルの HTML
+pub struct HtmlReport {
+    pub title: String,
+    pub files: Vec<HtmlFile>,
+}
+
+impl HtmlReport {
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html lang=\"ja\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n",
+            title = escape(&self.title)
+        );
+
+        out.push_str("<nav><ul>\n");
+        for (i, file) in self.files.iter().enumerate() {
+            out.push_str(&format!(
+                "<li><a href=\"#file-{i}\">{}</a><span class=\"summary\">{}</span></li>\n",
+                escape(&file.path),
+                escape(&file.summary)
+            ));
+        }
+        out.push_str("</ul></nav>\n");
+
+        for (i, file) in self.files.iter().enumerate() {
+            out.push_str(&format!(
+                "<section id=\"file-{i}\">\n<h2>{}<span class=\"summary\">{}</span></h2>\n{}</section>\n",
+                escape(&file.path),
+                escape(&file.summary),
+                file.body
+            ));
+        }
+
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+}
+
+fn note(text: &str) -> String {
+    format!("<p class=\"note\">{}</p>\n", escape(text))
+}
+
+/// ハンクを行番号付きの表にする。行番号は `start` の分だけずらし、コードはそれぞれの側のパスの拡張子で色付けする
+pub fn render(hunks: &[Hunk], old: &PatchTarget, new: &PatchTarget) -> String {
+    if hunks.is_empty() {
+        return note("差分はありません");
+    }
+
+    let old_offset = old.start.max(1) - 1;
+    let new_offset = new.start.max(1) - 1;
+    let mut old_highlighter = HighlightLines::new(syntax_for(old.path), &THEME);
+    let mut new_highlighter = HighlightLines::new(syntax_for(new.path), &THEME);
+
+    let mut out = String::from("<table>\n");
+    for hunk in hunks {
+        out.push_str(&format!(
+            "<tr class=\"hunk\"><td colspan=\"3\">{}</td></tr>\n",
+            escape(&hunk_header(hunk, old_offset, new_offset))
+        ));
+
+        for row in rows(&hunk.lines) {
+            let (class, old_no, new_no, code) = match row {
+                Row::Context((old_no, new_no), content) => {
+                    let code = highlight(&mut old_highlighter, content);
+                    // 両側の構文解析の状態を揃えておくため、新しい側にも同じ行を通す
+                    highlight(&mut new_highlighter, content);
+                    (
+                        "context",
+                        Some(old_no + old_offset),
+                        Some(new_no + new_offset),
+                        code,
+                    )
+                }
+                Row::Removed(lineno, content) => (
+                    "removed",
+                    Some(lineno + old_offset),
+                    None,
+                    highlight(&mut old_highlighter, content),
+                ),
+                Row::Added(lineno, content) => (
+                    "added",
+                    None,
+                    Some(lineno + new_offset),
+                    highlight(&mut new_highlighter, content),
+                ),
+            };
+            let number = |n: Option<usize>| n.map_or(String::new(), |n| n.to_string());
+            out.push_str(&format!(
+                "<tr class=\"{class}\"><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"code\">{code}</td></tr>\n",
+                number(old_no),
+                number(new_no)
+            ));
+        }
+    }
+    out.push_str("</table>\n");
+
+    out
+}
+
+enum Row<'a> {
+    Context((usize, usize), &'a str),
+    Removed(usize, &'a str),
+    Added(usize, &'a str),
+}
+
+/// unified diff と同じく、変更のまとまりごとに削除行 → 追加行の順へ並べ替える
+fn rows(lines: &[LineDiff]) -> Vec<Row<'_>> {
+    let mut out = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+
+    for line in lines {
+        match (&line.old, &line.new) {
+            (Some(old), Some(new)) if !line.is_change() => {
+                out.append(&mut removed);
+                out.append(&mut added);
+                out.push(Row::Context((old.lineno, new.lineno), old.content.as_str()));
+            }
+            (old, new) => {
+                if let Some(old) = old {
+                    removed.push(Row::Removed(old.lineno, old.content.as_str()));
+                }
+                if let Some(new) = new {
+                    added.push(Row::Added(new.lineno, new.content.as_str()));
+                }
+            }
+        }
+    }
+    out.append(&mut removed);
+    out.append(&mut added);
+
+    out
+}
+
+fn syntax_for(path: &str) -> &'static SyntaxReference {
+    Path::new(path)
+        .extension()
+        .and_then(|ext| SYNTAXES.find_syntax_by_extension(&ext.to_string_lossy()))
+        .unwrap_or_else(|| SYNTAXES.find_syntax_plain_text())
+}
+
+/// 構文解析に失敗した行は色を付けずにそのまま出す
+fn highlight(highlighter: &mut HighlightLines, content: &str) -> String {
+    let line = format!("{}\n", content);
+    highlighter
+        .highlight_line(&line, &SYNTAXES)
+        .ok()
+        .and_then(|regions| styled_line_to_highlighted_html(&regions, IncludeBackground::No).ok())
+        .map_or_else(|| escape(content), |html| html.replace('\n', ""))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::{ContextSize, Diff, DiffProvider};
+    use crate::utils::test_support::lines_of;
+
+    fn target(path: &str, start: usize) -> PatchTarget<'_> {
+        PatchTarget { path, start }
+    }
+
+    #[test]
+    fn renders_rows_with_file_line_numbers() {
+        let diff = Diff::new(
+            lines_of(&["fn a() {}", "let x = 1;", "fn c() {}"]),
+            lines_of(&["fn a() {}", "let x = 2;", "fn c() {}"]),
+        )
+        .with_context(ContextSize::Lines(1));
+
+        let html = render(&diff.hunks(), &target("a.rs", 10), &target("a.rs", 20));
+
+        assert!(html.contains("@@ -10,3 +20,3 @@"));
+        assert!(
+            html.contains("<tr class=\"removed\"><td class=\"num\">11</td><td class=\"num\"></td>")
+        );
+        assert!(
+            html.contains("<tr class=\"added\"><td class=\"num\"></td><td class=\"num\">21</td>")
+        );
+        // rust の構文で色付けされる
+        assert!(html.contains("<span style="));
+    }
+
+    #[test]
+    fn escapes_html_in_code_and_paths() {
+        let diff = Diff::new(lines_of(&["a"]), lines_of(&["<b>&"]));
+        let report = HtmlReport {
+            title: "main → <feature>".to_string(),
+            files: vec![HtmlFile {
+                path: "x.unknown".to_string(),
+                summary: String::new(),
+                body: render(
+                    &diff.hunks(),
+                    &target("x.unknown", 1),
+                    &target("x.unknown", 1),
+                ),
+            }],
+        };
+
+        let html = report.render();
+
+        assert!(html.contains("<title>main → &lt;feature&gt;</title>"));
+        assert!(html.contains("&lt;b&gt;&amp;"));
+        assert!(!html.contains("<b>&"));
+    }
+
+    #[test]
+    fn empty_diff_is_rendered_as_note() {
+        let diff = Diff::new(lines_of(&["a"]), lines_of(&["a"]));
+
+        let html = render(&diff.hunks(), &target("a.rs", 1), &target("a.rs", 1));
+
+        assert_eq!(html, "<p class=\"note\">差分はありません</p>\n");
+    }
+}
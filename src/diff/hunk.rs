@@ -0,0 +1,155 @@
+use super::LineDiff;
+
+/// 文脈行を含めた一続きの差分。開始行は 1 始まりで、行数が 0 の場合は直前の行番号になる
+#[derive(serde::Serialize)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    /// ハンクの直前にある、関数定義などの見出し行（git の funcname 相当）
+    pub header: Option<String>,
+    pub lines: Vec<LineDiff>,
+}
+
+impl Hunk {
+    /// 抽出した範囲の中での行番号を、ファイル全体での行番号にずらす。`old_start` / `new_start` は範囲の先頭行番号
+    pub fn at(mut self, old_start: usize, new_start: usize) -> Self {
+        let old_offset = old_start.max(1) - 1;
+        let new_offset = new_start.max(1) - 1;
+
+        self.old_start += old_offset;
+        self.new_start += new_offset;
+        for line in &mut self.lines {
+            if let Some(old) = &mut line.old {
+                old.lineno += old_offset;
+            }
+            if let Some(new) = &mut line.new {
+                new.lineno += new_offset;
+            }
+        }
+        self
+    }
+}
+
+/// ハンクが始まる位置。`row` は取り出した行の中での位置、`old_pos` / `new_pos` はそれより前にある行数
+pub struct HunkStart {
+    pub row: usize,
+    pub old_pos: usize,
+    pub new_pos: usize,
+}
+
+/// 文脈行を含めて取り出した行を、開始位置ごとのハンクに分ける
+pub fn build(rows: Vec<LineDiff>, starts: &[HunkStart], old: &[String]) -> Vec<Hunk> {
+    let total = rows.len();
+    let mut rows = rows.into_iter();
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(k, start)| {
+            let end = starts.get(k + 1).map_or(total, |next| next.row);
+            let lines: Vec<LineDiff> = rows.by_ref().take(end - start.row).collect();
+            let old_len = lines.iter().filter(|line| line.old.is_some()).count();
+            let new_len = lines.iter().filter(|line| line.new.is_some()).count();
+
+            Hunk {
+                old_start: start.old_pos + usize::from(old_len > 0),
+                old_len,
+                new_start: start.new_pos + usize::from(new_len > 0),
+                new_len,
+                header: function_header(old, start.old_pos.min(old.len())),
+                lines,
+            }
+        })
+        .collect()
+}
+
+/// `before` 行目より前で、行頭が英字・`_`・`$` で始まる最も近い行を探す（git の既定の判定と同じ）
+fn function_header(old: &[String], before: usize) -> Option<String> {
+    old[..before]
+        .iter()
+        .rev()
+        .find(|line| {
+            line.chars()
+                .next()
+                .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        })
+        .map(|line| line.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff::Diff;
+    use crate::utils::test_support::lines_of;
+
+    #[test]
+    fn separates_distant_changes_with_function_headers() {
+        let old = lines_of(&[
+            "fn first() {",
+            "    a();",
+            "    b();",
+            "    c();",
+            "}",
+            "fn second() {",
+            "    d();",
+            "    e();",
+            "}",
+        ]);
+        let mut new = old.clone();
+        new[2] = "    B();".to_string();
+        new[7] = "    E();".to_string();
+
+        let hunks = Diff::new(old, new).hunks_with_context(1);
+
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(
+            (
+                hunks[0].old_start,
+                hunks[0].old_len,
+                hunks[0].new_start,
+                hunks[0].new_len
+            ),
+            (2, 3, 2, 3)
+        );
+        assert_eq!(hunks[0].header.as_deref(), Some("fn first() {"));
+        assert_eq!((hunks[1].old_start, hunks[1].old_len), (7, 3));
+        assert_eq!(hunks[1].header.as_deref(), Some("fn second() {"));
+    }
+
+    #[test]
+    fn at_shifts_line_numbers_to_file_positions() {
+        let old = lines_of(&["a", "value = 1"]);
+        let new = lines_of(&["a", "value = 2"]);
+
+        let hunk = Diff::new(old, new)
+            .hunks_with_context(0)
+            .remove(0)
+            .at(10, 20);
+
+        assert_eq!((hunk.old_start, hunk.new_start), (11, 21));
+        let line = &hunk.lines[0];
+        assert_eq!(line.old.as_ref().map(|l| l.lineno), Some(11));
+        assert_eq!(line.new.as_ref().map(|l| l.lineno), Some(21));
+    }
+
+    #[test]
+    fn empty_side_starts_at_preceding_line() {
+        let old = lines_of(&["x", "y"]);
+        let new = lines_of(&["x", "y", "z"]);
+
+        let hunks = Diff::new(old, new).hunks_with_context(0);
+
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(
+            (
+                hunks[0].old_start,
+                hunks[0].old_len,
+                hunks[0].new_start,
+                hunks[0].new_len
+            ),
+            (2, 0, 3, 1)
+        );
+        assert_eq!(hunks[0].header.as_deref(), Some("y"));
+    }
+}
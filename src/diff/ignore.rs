@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use regex::Regex;
+
+/// 比較で無視する行の正規表現（git diff -I）。
+/// 両側の行が同じパターンに一致すれば、内容が異なっていても同じ行として扱う
+#[derive(Clone, Debug, Default)]
+pub struct IgnorePatterns(Arc<[Regex]>);
+
+impl IgnorePatterns {
+    pub fn new<S: AsRef<str>>(patterns: impl IntoIterator<Item = S>) -> Result<Self, regex::Error> {
+        let patterns = patterns
+            .into_iter()
+            .map(|pattern| Regex::new(pattern.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self(patterns.into()))
+    }
+
+    /// 一致したパターンごとに共通の比較用の文字列を返す。
+    /// NUL はテキストファイルの行に現れないため、実際の行と衝突しない
+    pub(super) fn key(&self, line: &str) -> Option<String> {
+        self.0
+            .iter()
+            .position(|pattern| pattern.is_match(line))
+            .map(|index| format!("\0ignore-{}", index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_matching_same_pattern_get_same_key() {
+        let patterns =
+            IgnorePatterns::new([r"^// generated at", r"[0-9a-f]{8}-[0-9a-f]{4}"]).unwrap();
+
+        let old = patterns.key("// generated at 2024-01-01");
+        let new = patterns.key("// generated at 2025-06-30");
+        assert!(old.is_some());
+        assert_eq!(old, new);
+
+        assert_ne!(old, patterns.key("id = 123e4567-e89b-12d3"));
+        assert_eq!(patterns.key("fn main() {}"), None);
+    }
+
+    #[test]
+    fn rejects_invalid_pattern() {
+        assert!(IgnorePatterns::new(["("]).is_err());
+    }
+}
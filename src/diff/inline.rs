@@ -0,0 +1,125 @@
+use prettydiff::basic::{self, DiffOp};
+use prettydiff::text::split_words;
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SpanKind {
+    Added,
+    Removed,
+}
+
+/// 行内で変更された範囲。`start` / `end` は文字単位のオフセット（end は含まない）
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct InlineSpan {
+    pub start: usize,
+    pub end: usize,
+    pub kind: SpanKind,
+}
+
+/// 単語の数の積がこれより大きい行は、計算量を抑えるため行全体を変更範囲にする
+const MAX_CELLS: usize = 250_000;
+
+/// `DiffProvider::words` と同じ単語分割で old / new それぞれの変更範囲を求める
+pub fn word_spans(old: &str, new: &str) -> (Vec<InlineSpan>, Vec<InlineSpan>) {
+    let old_words: Vec<&str> = split_words(old).collect();
+    let new_words: Vec<&str> = split_words(new).collect();
+    if old_words.len().saturating_mul(new_words.len()) > MAX_CELLS {
+        return (
+            whole_line(&old_words, SpanKind::Removed),
+            whole_line(&new_words, SpanKind::Added),
+        );
+    }
+
+    let mut old_spans = Vec::new();
+    let mut new_spans = Vec::new();
+    let mut old_pos = 0;
+    let mut new_pos = 0;
+
+    for op in basic::diff(&old_words, &new_words) {
+        match op {
+            DiffOp::Equal(words) => {
+                old_pos += char_len(words);
+                new_pos += char_len(words);
+            }
+            DiffOp::Remove(words) => {
+                push_span(&mut old_spans, &mut old_pos, words, SpanKind::Removed);
+            }
+            DiffOp::Insert(words) => {
+                push_span(&mut new_spans, &mut new_pos, words, SpanKind::Added);
+            }
+            DiffOp::Replace(old_part, new_part) => {
+                push_span(&mut old_spans, &mut old_pos, old_part, SpanKind::Removed);
+                push_span(&mut new_spans, &mut new_pos, new_part, SpanKind::Added);
+            }
+        }
+    }
+
+    (old_spans, new_spans)
+}
+
+fn whole_line(words: &[&str], kind: SpanKind) -> Vec<InlineSpan> {
+    let mut spans = Vec::new();
+    if !words.is_empty() {
+        push_span(&mut spans, &mut 0, words, kind);
+    }
+    spans
+}
+
+fn char_len(words: &[&str]) -> usize {
+    words.iter().map(|w| w.chars().count()).sum()
+}
+
+fn push_span(spans: &mut Vec<InlineSpan>, pos: &mut usize, words: &[&str], kind: SpanKind) {
+    let start = *pos;
+    *pos += char_len(words);
+
+    match spans.last_mut() {
+        Some(last) if last.end == start => last.end = *pos,
+        _ => spans.push(InlineSpan {
+            start,
+            end: *pos,
+            kind,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(start: usize, end: usize, kind: SpanKind) -> InlineSpan {
+        InlineSpan { start, end, kind }
+    }
+
+    #[test]
+    fn marks_changed_word_only() {
+        let (old, new) = word_spans("let value = 1;", "let value = 2;");
+        assert_eq!(old, vec![span(12, 13, SpanKind::Removed)]);
+        assert_eq!(new, vec![span(12, 13, SpanKind::Added)]);
+    }
+
+    #[test]
+    fn counts_offsets_in_chars() {
+        let (old, new) = word_spans("名前 = 太郎", "名前 = 花子");
+        assert_eq!(old, vec![span(5, 7, SpanKind::Removed)]);
+        assert_eq!(new, vec![span(5, 7, SpanKind::Added)]);
+    }
+
+    #[test]
+    fn insertion_leaves_old_side_empty() {
+        let (old, new) = word_spans("foo(a)", "foo(a, b)");
+        assert!(old.is_empty());
+        assert_eq!(new, vec![span(5, 8, SpanKind::Added)]);
+    }
+
+    #[test]
+    fn long_lines_are_marked_as_a_whole() {
+        let old = "a ".repeat(600);
+        let new = "b ".repeat(600);
+
+        let (old_spans, new_spans) = word_spans(&old, &new);
+
+        assert_eq!(old_spans, vec![span(0, 1200, SpanKind::Removed)]);
+        assert_eq!(new_spans, vec![span(0, 1200, SpanKind::Added)]);
+    }
+}
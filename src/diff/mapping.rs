@@ -0,0 +1,164 @@
+use std::fmt;
+
+use super::{Diff, LineOp};
+
+/// 旧側の行範囲を、ファイル全体の差分をたどって新側の行範囲に対応付けた結果 (git log -L 相当)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineMapping {
+    /// 新側の範囲 (1 始まり、両端を含む)
+    pub start: usize,
+    pub end: usize,
+    /// 旧側の範囲のうち、新側に変更なく残っている行数
+    pub matched: usize,
+    /// 旧側の範囲の行数
+    pub total: usize,
+}
+
+impl LineMapping {
+    /// 変更なく残っている行が半分に満たなければ、対応付けが正しいとは言い切れない
+    pub fn is_confident(&self) -> bool {
+        self.matched * 2 >= self.total
+    }
+}
+
+impl fmt::Display for LineMapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "L{}-L{} ({} 行中 {} 行が一致)",
+            self.start, self.end, self.total, self.matched
+        )
+    }
+}
+
+impl Diff {
+    /// 旧側の `start`..=`end` 行が、新側のどの範囲に移ったかを求める。
+    /// 範囲の端が変更された行にかかる場合は、置き換え後の行を含むよう広げる。
+    /// 範囲の行がすべて削除されていれば `None`
+    pub fn map_lines(&self, start: usize, end: usize) -> Option<LineMapping> {
+        // 0 始まりの半開区間にする
+        let first = start.max(1) - 1;
+        let last = end.min(self.old.len());
+        if first >= last {
+            return None;
+        }
+
+        let mut new_start = None;
+        let mut new_end = 0;
+        let mut matched = 0;
+        let mut j = 0;
+        // 範囲内の行を削除した直後の追加は、その行の置き換えとみなす
+        let mut removed_in_range = false;
+        for op in self.line_ops() {
+            let (old, new, equal) = match op {
+                LineOp::Equal(old, new) => (old, new, true),
+                LineOp::Replace(old, new) => (old, new, false),
+                LineOp::Remove(old) => (old, j..j, false),
+                LineOp::Insert(new) => {
+                    if removed_in_range {
+                        new_end = new_end.max(new.end);
+                    }
+                    j = new.end;
+                    continue;
+                }
+            };
+            j = new.end;
+            removed_in_range = false;
+            if old.end <= first || old.start >= last {
+                continue;
+            }
+            removed_in_range = !equal;
+
+            if equal {
+                let from = old.start.max(first);
+                let to = old.end.min(last);
+                matched += to - from;
+                new_start.get_or_insert(new.start + (from - old.start));
+                new_end = new.start + (to - old.start);
+            } else {
+                new_start.get_or_insert(new.start);
+                new_end = new_end.max(new.end);
+            }
+        }
+
+        let new_start = new_start?;
+        (new_end > new_start).then_some(LineMapping {
+            start: new_start + 1,
+            end: new_end,
+            matched,
+            total: last - first,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_support::lines_of;
+
+    #[test]
+    fn follows_lines_shifted_by_insertions() {
+        let old = lines_of(&["a", "fn f() {", "x", "}", "z"]);
+        let new = lines_of(&["new", "new", "a", "fn f() {", "x", "}", "z"]);
+
+        let mapping = Diff::new(old, new).map_lines(2, 4).unwrap();
+
+        assert_eq!(
+            mapping,
+            LineMapping {
+                start: 4,
+                end: 6,
+                matched: 3,
+                total: 3
+            }
+        );
+        assert!(mapping.is_confident());
+    }
+
+    #[test]
+    fn includes_changed_and_inserted_lines_inside_range() {
+        let old = lines_of(&["a", "fn f() {", "x", "}", "z"]);
+        let new = lines_of(&["a", "fn f() {", "X", "y", "}", "z"]);
+
+        let mapping = Diff::new(old, new).map_lines(2, 4).unwrap();
+
+        assert_eq!((mapping.start, mapping.end), (2, 5));
+        assert_eq!(mapping.matched, 2);
+    }
+
+    #[test]
+    fn widens_to_replacement_at_range_edge() {
+        let old = lines_of(&["a", "b", "c", "d"]);
+        let new = lines_of(&["a", "B1", "B2", "c", "d"]);
+
+        let mapping = Diff::new(old, new).map_lines(2, 3).unwrap();
+
+        assert_eq!((mapping.start, mapping.end), (2, 4));
+        assert!(mapping.is_confident());
+
+        let old = lines_of(&["a", "b", "c"]);
+        let new = lines_of(&["a", "B", "c"]);
+        let mapping = Diff::new(old, new).map_lines(2, 2).unwrap();
+        assert_eq!((mapping.start, mapping.end, mapping.matched), (2, 2, 0));
+    }
+
+    #[test]
+    fn low_confidence_when_most_lines_changed() {
+        let old = lines_of(&["a", "b", "c", "d", "e"]);
+        let new = lines_of(&["a", "B", "C", "D", "e"]);
+
+        let mapping = Diff::new(old, new).map_lines(2, 5).unwrap();
+
+        assert_eq!((mapping.start, mapping.end), (2, 5));
+        assert!(!mapping.is_confident());
+        assert_eq!(mapping.to_string(), "L2-L5 (4 行中 1 行が一致)");
+    }
+
+    #[test]
+    fn deleted_range_has_no_mapping() {
+        let old = lines_of(&["a", "b", "c", "d"]);
+        let new = lines_of(&["a", "d"]);
+
+        assert_eq!(Diff::new(old, new).map_lines(2, 3), None);
+    }
+}
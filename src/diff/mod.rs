@@ -1,11 +1,53 @@
-use prettydiff::{basic::DiffOp, diff_chars, diff_lines, diff_slice, diff_words};
+mod algorithm;
+#[cfg(test)]
+mod benches;
+mod context;
+mod html;
+mod hunk;
+mod ignore;
+mod inline;
+mod mapping;
+mod moved;
+mod pairing;
+mod piecewise;
+mod search;
+mod side_by_side;
+mod stats;
+mod three_way;
+mod unified;
+mod whitespace;
 
-#[derive(serde::Serialize, Clone, Copy)]
+pub use algorithm::DiffAlgorithm;
+pub use context::ContextSize;
+pub use html::{HtmlFile, HtmlReport};
+pub use hunk::Hunk;
+use hunk::HunkStart;
+pub use ignore::IgnorePatterns;
+pub use inline::InlineSpan;
+pub use piecewise::{DiffPiece, PiecewiseDiff};
+pub use search::{RegionMatch, find_region};
+pub use side_by_side::Overflow;
+pub use stats::DiffStats;
+pub use three_way::ThreeWayDiff;
+pub use unified::PatchTarget;
+pub use whitespace::WhitespaceMode;
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::sync::Arc;
+
+use prettydiff::basic::{DiffOp, SliceChangeset};
+use prettydiff::owo_colors::OwoColorize;
+use prettydiff::{diff_chars, diff_words};
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum DiffType {
     Added,
     Removed,
     Replaced,
+    Moved,
     Equal,
 }
 
@@ -14,6 +56,21 @@ pub struct LineInfo {
     pub lineno: usize,
     pub content: String,
     pub diff_type: DiffType,
+    pub spans: Option<Vec<InlineSpan>>,
+    /// 移動元と移動先の行で共通の ID
+    pub move_id: Option<usize>,
+}
+
+impl LineInfo {
+    fn new(lineno: usize, content: &str, diff_type: DiffType) -> Self {
+        Self {
+            lineno,
+            content: content.to_string(),
+            diff_type,
+            spans: None,
+            move_id: None,
+        }
+    }
 }
 
 #[derive(serde::Serialize)]
@@ -22,22 +79,121 @@ pub struct LineDiff {
     pub new: Option<LineInfo>,
 }
 
+impl LineDiff {
+    pub fn is_change(&self) -> bool {
+        [&self.old, &self.new]
+            .into_iter()
+            .flatten()
+            .any(|line| line.diff_type != DiffType::Equal)
+    }
+}
+
 pub trait DiffProvider {
     fn slice(&self) -> String;
     fn chars(&self) -> String;
     fn words(&self) -> String;
     fn lines(&self) -> String;
     fn lines_structured(&self) -> Vec<LineDiff>;
+    fn hunks(&self) -> Vec<Hunk>;
 }
 
 pub struct Diff {
-    old: Vec<String>,
-    new: Vec<String>,
+    old: Arc<[String]>,
+    new: Arc<[String]>,
+    algorithm: DiffAlgorithm,
+    whitespace: WhitespaceMode,
+    ignore_blank_lines: bool,
+    ignore_cr_at_eol: bool,
+    ignore_patterns: IgnorePatterns,
+    context: ContextSize,
+}
+
+/// 1 行分の対応。変更のない行は 0 始まりの行番号だけを持つ
+enum Row {
+    Unchanged(Option<usize>, Option<usize>),
+    Changed(LineDiff),
+}
+
+impl Row {
+    /// old / new それぞれの側に行があるか
+    fn sides(&self) -> (bool, bool) {
+        match self {
+            Row::Unchanged(old, new) => (old.is_some(), new.is_some()),
+            Row::Changed(line) => (line.old.is_some(), line.new.is_some()),
+        }
+    }
+}
+
+/// 0 始まりの行番号の範囲で表した差分操作
+enum LineOp {
+    Equal(Range<usize>, Range<usize>),
+    Insert(Range<usize>),
+    Remove(Range<usize>),
+    Replace(Range<usize>, Range<usize>),
 }
 
 impl Diff {
-    pub fn new(old: Vec<String>, new: Vec<String>) -> Self {
-        Self { old, new }
+    /// `Vec<String>` のほか、他の差分と共有している `Arc<[String]>` もそのまま受け取る
+    pub fn new(old: impl Into<Arc<[String]>>, new: impl Into<Arc<[String]>>) -> Self {
+        Self {
+            old: old.into(),
+            new: new.into(),
+            algorithm: DiffAlgorithm::default(),
+            whitespace: WhitespaceMode::default(),
+            ignore_blank_lines: false,
+            ignore_cr_at_eol: false,
+            ignore_patterns: IgnorePatterns::default(),
+            context: ContextSize::default(),
+        }
+    }
+
+    pub fn with_algorithm(mut self, algorithm: DiffAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    pub fn with_whitespace(mut self, whitespace: WhitespaceMode) -> Self {
+        self.whitespace = whitespace;
+        self
+    }
+
+    pub fn with_ignore_blank_lines(mut self, ignore_blank_lines: bool) -> Self {
+        self.ignore_blank_lines = ignore_blank_lines;
+        self
+    }
+
+    /// 行末の CR だけが異なる行を同じ行として扱う。指定しなければ、CRLF と LF の行は別の行になる
+    pub fn with_ignore_cr_at_eol(mut self, ignore_cr_at_eol: bool) -> Self {
+        self.ignore_cr_at_eol = ignore_cr_at_eol;
+        self
+    }
+
+    /// 両側の行が同じパターンに一致すれば、内容が異なっていても同じ行として扱う
+    pub fn with_ignore_patterns(mut self, ignore_patterns: IgnorePatterns) -> Self {
+        self.ignore_patterns = ignore_patterns;
+        self
+    }
+
+    pub fn with_context(mut self, context: ContextSize) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// この差分の old / new をベースブランチ・比較対象として、マージベースからの 3 方向の比較にする。
+    /// 比較の設定はこの差分と同じものを使う
+    pub fn three_way(&self, merge_base: impl Into<Arc<[String]>>) -> ThreeWayDiff {
+        let merge_base = merge_base.into();
+        let from_merge_base = |side: &Arc<[String]>| Diff {
+            old: merge_base.clone(),
+            new: side.clone(),
+            ignore_patterns: self.ignore_patterns.clone(),
+            ..*self
+        };
+        ThreeWayDiff::new(
+            from_merge_base(&self.old),
+            from_merge_base(&self.new),
+            self.context,
+        )
     }
 
     pub fn to_string(&self) -> (String, String) {
@@ -46,130 +202,270 @@ impl Diff {
         (old, new)
     }
 
-    pub fn lines_structured_with_context(&self, context: usize) -> Vec<LineDiff> {
-        let old_refs: Vec<&str> = self.old.iter().map(|s| s.as_str()).collect();
-        let new_refs: Vec<&str> = self.new.iter().map(|s| s.as_str()).collect();
-        let changeset = diff_slice(&old_refs, &new_refs);
-
-        let mut raw = Vec::new();
-        let mut old_lineno = 1;
-        let mut new_lineno = 1;
-
-        for change in changeset.diff {
-            match change {
-                DiffOp::Insert(lines) => {
-                    for line in lines {
-                        raw.push((
-                            LineDiff {
-                                old: None,
-                                new: Some(LineInfo {
-                                    lineno: new_lineno,
-                                    content: line.to_string(),
-                                    diff_type: DiffType::Added,
-                                }),
-                            },
-                            true,
-                        ));
-                        new_lineno += 1;
-                    }
+    /// 比較用の行。改行コードと空白、無視するパターンの扱いを反映する
+    fn key<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let line = match self.ignore_cr_at_eol {
+            true => line.strip_suffix('\r').unwrap_or(line),
+            false => line,
+        };
+        match self.ignore_patterns.key(line) {
+            Some(key) => Cow::Owned(key),
+            None => self.whitespace.normalize(line),
+        }
+    }
+
+    /// 空白の扱いを反映した比較用の行で対応付けを行う
+    fn line_ops(&self) -> Vec<LineOp> {
+        let old_keys: Vec<Cow<str>> = self.old.iter().map(|line| self.key(line)).collect();
+        let new_keys: Vec<Cow<str>> = self.new.iter().map(|line| self.key(line)).collect();
+
+        let mut ops = Vec::new();
+        let mut i = 0;
+        let mut j = 0;
+
+        for op in self.algorithm.diff(&old_keys, &new_keys) {
+            let op = match op {
+                DiffOp::Equal(a) => LineOp::Equal(i..i + a.len(), j..j + a.len()),
+                DiffOp::Insert(b) => LineOp::Insert(j..j + b.len()),
+                DiffOp::Remove(a) => LineOp::Remove(i..i + a.len()),
+                DiffOp::Replace(a, b) => LineOp::Replace(i..i + a.len(), j..j + b.len()),
+            };
+            match &op {
+                LineOp::Equal(old, new) | LineOp::Replace(old, new) => {
+                    i = old.end;
+                    j = new.end;
                 }
-                DiffOp::Remove(lines) => {
-                    for line in lines {
-                        raw.push((
-                            LineDiff {
-                                old: Some(LineInfo {
-                                    lineno: old_lineno,
-                                    content: line.to_string(),
-                                    diff_type: DiffType::Removed,
-                                }),
-                                new: None,
-                            },
-                            true,
-                        ));
-                        old_lineno += 1;
+                LineOp::Insert(new) => j = new.end,
+                LineOp::Remove(old) => i = old.end,
+            }
+            ops.push(op);
+        }
+
+        ops
+    }
+
+    /// 空行だけの追加・削除は、無視する設定なら変更として扱わない
+    fn is_ignored_blank(&self, lines: &[String]) -> bool {
+        self.ignore_blank_lines && lines.iter().all(|line| line.trim().is_empty())
+    }
+
+    /// 差分操作を 1 行ずつに展開する。変更のない行は行番号だけを持ち、中身は必要になるまで取り出さない
+    fn rows(&self) -> impl Iterator<Item = Row> + '_ {
+        self.line_ops()
+            .into_iter()
+            .flat_map(move |op| -> Box<dyn Iterator<Item = Row> + '_> {
+                match op {
+                    LineOp::Equal(old, new) => {
+                        Box::new(old.zip(new).map(|(i, j)| Row::Unchanged(Some(i), Some(j))))
                     }
-                }
-                DiffOp::Equal(lines) => {
-                    for line in lines {
-                        raw.push((
-                            LineDiff {
-                                old: Some(LineInfo {
-                                    lineno: old_lineno,
-                                    content: line.to_string(),
-                                    diff_type: DiffType::Equal,
-                                }),
-                                new: Some(LineInfo {
-                                    lineno: new_lineno,
-                                    content: line.to_string(),
-                                    diff_type: DiffType::Equal,
-                                }),
-                            },
-                            false,
-                        ));
-                        old_lineno += 1;
-                        new_lineno += 1;
+                    LineOp::Insert(new) if self.is_ignored_blank(&self.new[new.clone()]) => {
+                        Box::new(new.map(|j| Row::Unchanged(None, Some(j))))
                     }
-                }
-                DiffOp::Replace(old_lines, new_lines) => {
-                    let count = old_lines.len().max(new_lines.len());
-
-                    for i in 0..count {
-                        let old = old_lines.get(i);
-                        let new = new_lines.get(i);
-
-                        let diff_type = match (old, new) {
-                            (Some(_), Some(_)) => DiffType::Replaced,
-                            (Some(_), None) => DiffType::Removed,
-                            (None, Some(_)) => DiffType::Added,
-                            (None, None) => continue,
-                        };
-
-                        raw.push((
-                            LineDiff {
-                                old: old.map(|line| LineInfo {
-                                    lineno: old_lineno + i,
-                                    content: line.to_string(),
-                                    diff_type,
-                                }),
-                                new: new.map(|line| LineInfo {
-                                    lineno: new_lineno + i,
-                                    content: line.to_string(),
-                                    diff_type,
-                                }),
-                            },
-                            true,
-                        ));
+                    LineOp::Remove(old) if self.is_ignored_blank(&self.old[old.clone()]) => {
+                        Box::new(old.map(|i| Row::Unchanged(Some(i), None)))
                     }
+                    LineOp::Insert(new) => Box::new(new.map(|j| {
+                        Row::Changed(LineDiff {
+                            old: None,
+                            new: Some(LineInfo::new(j + 1, &self.new[j], DiffType::Added)),
+                        })
+                    })),
+                    LineOp::Remove(old) => Box::new(old.map(|i| {
+                        Row::Changed(LineDiff {
+                            old: Some(LineInfo::new(i + 1, &self.old[i], DiffType::Removed)),
+                            new: None,
+                        })
+                    })),
+                    LineOp::Replace(old, new) => {
+                        Box::new(self.replaced_rows(old, new).into_iter().map(Row::Changed))
+                    }
+                }
+            })
+    }
+
+    /// 置換ブロック内の行を内容の近さで対応付け、対応する行どうしには行内の変更範囲を付ける
+    fn replaced_rows(&self, old_range: Range<usize>, new_range: Range<usize>) -> Vec<LineDiff> {
+        let old_lines = &self.old[old_range.clone()];
+        let new_lines = &self.new[new_range.clone()];
+        let mut rows = Vec::new();
+
+        for (old_index, new_index) in pairing::pair_lines(old_lines, new_lines) {
+            let old = old_index.map(|i| &old_lines[i]);
+            let new = new_index.map(|i| &new_lines[i]);
 
-                    old_lineno += old_lines.len();
-                    new_lineno += new_lines.len();
+            let diff_type = match (old, new) {
+                (Some(_), Some(_)) => DiffType::Replaced,
+                (Some(_), None) => DiffType::Removed,
+                (None, Some(_)) => DiffType::Added,
+                (None, None) => continue,
+            };
+
+            let (old_spans, new_spans) = match (old, new) {
+                (Some(old), Some(new)) => {
+                    let (old_spans, new_spans) = inline::word_spans(old, new);
+                    (Some(old_spans), Some(new_spans))
                 }
-            }
+                _ => (None, None),
+            };
+
+            rows.push(LineDiff {
+                old: old_index.map(|i| LineInfo {
+                    spans: old_spans,
+                    ..LineInfo::new(old_range.start + i + 1, &old_lines[i], diff_type)
+                }),
+                new: new_index.map(|i| LineInfo {
+                    spans: new_spans,
+                    ..LineInfo::new(new_range.start + i + 1, &new_lines[i], diff_type)
+                }),
+            });
         }
 
-        let mut needed = vec![false; raw.len()];
-        for (i, (_, is_change)) in raw.iter().enumerate() {
-            if *is_change {
-                let start = i.saturating_sub(context);
-                let end = (i + context + 1).min(raw.len());
-                needed
-                    .iter_mut()
-                    .take(end)
-                    .skip(start)
-                    .for_each(|flag| *flag = true);
+        rows
+    }
+
+    fn unchanged_row(&self, old: Option<usize>, new: Option<usize>) -> LineDiff {
+        LineDiff {
+            old: old.map(|i| LineInfo::new(i + 1, &self.old[i], DiffType::Equal)),
+            new: new.map(|j| LineInfo::new(j + 1, &self.new[j], DiffType::Equal)),
+        }
+    }
+
+    /// 変更行と、その前後 `context` 行だけを取り出し、ハンクの開始位置とともに返す。
+    /// 省略する行は最大 `context` 行しか保持しないため、大きなファイルでも変更のない行を溜め込まない
+    fn collect_rows(&self, context: usize) -> (Vec<LineDiff>, Vec<HunkStart>) {
+        let mut rows = Vec::new();
+        let mut starts = Vec::new();
+        // まだ出力していない変更のない行と、その行より前にある old / new の行数
+        let mut pending: VecDeque<(usize, usize, Option<usize>, Option<usize>)> = VecDeque::new();
+        let mut trailing = 0;
+        let mut gap = true;
+        let mut old_pos = 0;
+        let mut new_pos = 0;
+
+        for row in self.rows() {
+            let (has_old, has_new) = row.sides();
+            match row {
+                Row::Unchanged(old, new) if trailing > 0 => {
+                    rows.push(self.unchanged_row(old, new));
+                    trailing -= 1;
+                }
+                Row::Unchanged(old, new) => {
+                    pending.push_back((old_pos, new_pos, old, new));
+                    if pending.len() > context {
+                        pending.pop_front();
+                        gap = true;
+                    }
+                }
+                Row::Changed(line) => {
+                    if gap {
+                        let (old_pos, new_pos) = pending
+                            .front()
+                            .map_or((old_pos, new_pos), |&(old_pos, new_pos, _, _)| {
+                                (old_pos, new_pos)
+                            });
+                        starts.push(HunkStart {
+                            row: rows.len(),
+                            old_pos,
+                            new_pos,
+                        });
+                        gap = false;
+                    }
+                    for (_, _, old, new) in pending.drain(..) {
+                        rows.push(self.unchanged_row(old, new));
+                    }
+                    rows.push(line);
+                    trailing = context;
+                }
             }
+            old_pos += usize::from(has_old);
+            new_pos += usize::from(has_new);
         }
 
-        raw.into_iter()
-            .enumerate()
-            .filter_map(|(i, (line, _))| if needed[i] { Some(line) } else { None })
+        moved::mark_moved_blocks(&mut rows);
+        (rows, starts)
+    }
+
+    /// 文脈で絞り込まない、ファイル全体の行ごとの対応
+    fn structured_lines(&self) -> Vec<LineDiff> {
+        let mut rows: Vec<LineDiff> = self
+            .rows()
+            .map(|row| match row {
+                Row::Unchanged(old, new) => self.unchanged_row(old, new),
+                Row::Changed(line) => line,
+            })
+            .collect();
+        moved::mark_moved_blocks(&mut rows);
+        rows
+    }
+
+    /// 追加・削除・置換された行数と一致率を返す
+    pub fn stats(&self) -> DiffStats {
+        let (rows, _) = self.collect_rows(0);
+        DiffStats::from_rows(&rows, self.old.len() + self.new.len())
+    }
+
+    /// 行番号と構文の色付きの表にした HTML を返す。差分がなければその旨の一文
+    pub fn html(&self, old: &PatchTarget, new: &PatchTarget) -> String {
+        html::render(&self.hunks(), old, new)
+    }
+
+    /// 行番号を含まないパッチの各行。同じ変更を別の位置に当てたパッチ同士の比較に使う
+    pub fn patch_lines(&self) -> Vec<String> {
+        unified::render_without_line_numbers(&self.hunks())
+    }
+
+    /// 端末向けに左右 2 列で並べた差分を返す
+    pub fn side_by_side(&self, width: usize, overflow: Overflow) -> String {
+        side_by_side::render(&self.lines_structured(), width, overflow)
+    }
+
+    /// lines モードと同じ表示を、文脈行数で区切ったハンクごとに返す
+    pub fn lines_in_hunks(&self, old_start: usize, new_start: usize) -> String {
+        let old_offset = old_start.max(1) - 1;
+        let new_offset = new_start.max(1) - 1;
+
+        self.hunks()
+            .into_iter()
+            .map(|hunk| {
+                let header = unified::hunk_header(&hunk, old_offset, new_offset);
+                format!("{}\n{}", header.dimmed(), render_lines(hunk.lines))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn hunks_with_context(&self, context: usize) -> Vec<Hunk> {
+        let (rows, starts) = self.collect_rows(context);
+        hunk::build(rows, &starts, &self.old)
+    }
+
+    pub fn lines_structured_with_context(&self, context: usize) -> Vec<LineDiff> {
+        self.hunks_with_context(context)
+            .into_iter()
+            .flat_map(|hunk| hunk.lines)
             .collect()
     }
 }
 
 impl DiffProvider for Diff {
     fn slice(&self) -> String {
-        let result = diff_slice(&self.old, &self.new);
+        let diff = self
+            .line_ops()
+            .into_iter()
+            .map(|op| match op {
+                LineOp::Equal(old, _) => DiffOp::Equal(&self.old[old]),
+                LineOp::Insert(new) if self.is_ignored_blank(&self.new[new.clone()]) => {
+                    DiffOp::Equal(&self.new[new])
+                }
+                LineOp::Remove(old) if self.is_ignored_blank(&self.old[old.clone()]) => {
+                    DiffOp::Equal(&self.old[old])
+                }
+                LineOp::Insert(new) => DiffOp::Insert(&self.new[new]),
+                LineOp::Remove(old) => DiffOp::Remove(&self.old[old]),
+                LineOp::Replace(old, new) => DiffOp::Replace(&self.old[old], &self.new[new]),
+            })
+            .collect();
+        let result = SliceChangeset { diff };
         let lines: Vec<String> = format!("{result}")
             .lines()
             .filter(|line| *line != "[" && *line != "]")
@@ -194,11 +490,290 @@ impl DiffProvider for Diff {
     }
 
     fn lines(&self) -> String {
-        let (old, new) = self.to_string();
-        diff_lines(&old, &new).format()
+        render_lines(self.structured_lines())
     }
 
     fn lines_structured(&self) -> Vec<LineDiff> {
-        self.lines_structured_with_context(3)
+        self.lines_structured_with_context(self.context.lines())
+    }
+
+    fn hunks(&self) -> Vec<Hunk> {
+        self.hunks_with_context(self.context.lines())
+    }
+}
+
+/// lines モードの表示。行ごとに色を付けて旧→新を 1 列に並べる
+fn render_lines(rows: Vec<LineDiff>) -> String {
+    let render = |line: &LineInfo, is_old: bool| match (line.diff_type, is_old) {
+        (DiffType::Moved, true) => format!(" {}", line.content.magenta().strikethrough()),
+        (DiffType::Moved, false) => format!(" {}", line.content.cyan()),
+        (_, true) => format!(" {}", line.content.red().strikethrough()),
+        (_, false) => format!(" {}", line.content.green()),
+    };
+
+    // prettydiff と同様に、変更のまとまりごとに削除行 → 追加行の順で出力する
+    let mut out = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    for line in rows {
+        if !line.is_change() {
+            out.append(&mut removed);
+            out.append(&mut added);
+            if let Some(line) = line.old.or(line.new) {
+                out.push(line.content);
+            }
+            continue;
+        }
+        if let Some(old) = &line.old {
+            removed.push(render(old, true));
+        }
+        if let Some(new) = &line.new {
+            added.push(render(new, false));
+        }
+    }
+    out.append(&mut removed);
+    out.append(&mut added);
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_support::lines_of;
+
+    #[test]
+    fn lines_matches_prettydiff_for_myers() {
+        let old = lines_of(&["a", "b", "c", "d"]);
+        let new = lines_of(&["a", "x", "c", "e", "f"]);
+        let diff = Diff::new(old.clone(), new.clone());
+
+        let expected = prettydiff::diff_lines(&old.join("\n"), &new.join("\n")).format();
+        assert_eq!(diff.lines(), expected);
+    }
+
+    #[test]
+    fn lines_structured_respects_algorithm() {
+        let old = lines_of(&["x", "fn a() {", "}", "y", "fn b() {", "}"]);
+        let new = lines_of(&["fn b() {", "}", "x", "fn a() {", "}", "y"]);
+
+        let equal_lines = |algorithm: DiffAlgorithm| {
+            Diff::new(old.clone(), new.clone())
+                .with_algorithm(algorithm)
+                .lines_structured_with_context(old.len())
+                .into_iter()
+                .filter_map(|line| line.old)
+                .filter(|old| matches!(old.diff_type, DiffType::Equal))
+                .map(|old| old.content)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            equal_lines(DiffAlgorithm::Patience),
+            vec!["x", "fn a() {", "}", "y"]
+        );
+    }
+
+    #[test]
+    fn replaced_lines_carry_inline_spans() {
+        let diff = Diff::new(lines_of(&["let a = 1;"]), lines_of(&["let a = 2;"]));
+        let lines = diff.lines_structured();
+
+        let old = lines[0].old.as_ref().unwrap();
+        let new = lines[0].new.as_ref().unwrap();
+        assert!(matches!(old.diff_type, DiffType::Replaced));
+        assert_eq!(
+            old.spans.as_ref().map(|s| (s[0].start, s[0].end)),
+            Some((8, 9))
+        );
+        assert_eq!(
+            new.spans.as_ref().map(|s| (s[0].start, s[0].end)),
+            Some((8, 9))
+        );
+    }
+
+    #[test]
+    fn moved_line_is_tagged_on_both_sides() {
+        let body = ["fn main() {", "    run();", "}"];
+        let moved = "use crate::config::load_configuration;";
+        let mut old = lines_of(&[moved]);
+        old.extend(lines_of(&body));
+        let mut new = lines_of(&body);
+        new.push(moved.to_string());
+
+        let lines = Diff::new(old, new).lines_structured();
+        let moved_lines: Vec<_> = lines
+            .iter()
+            .flat_map(|line| [&line.old, &line.new])
+            .flatten()
+            .filter(|line| line.diff_type == DiffType::Moved)
+            .map(|line| (line.lineno, line.move_id))
+            .collect();
+
+        assert_eq!(moved_lines, vec![(1, Some(1)), (4, Some(1))]);
+    }
+
+    #[test]
+    fn whitespace_mode_aligns_reindented_lines_but_keeps_content() {
+        let old = lines_of(&["if ok {", "run();", "}"]);
+        let new = lines_of(&["if ok {", "    run();", "}"]);
+
+        let lines = Diff::new(old, new)
+            .with_whitespace(WhitespaceMode::IgnoreAll)
+            .structured_lines();
+
+        assert!(lines.iter().all(|line| !line.is_change()));
+        assert_eq!(lines[1].new.as_ref().unwrap().content, "    run();");
+    }
+
+    #[test]
+    fn ignore_blank_lines_hides_blank_only_changes() {
+        let old = lines_of(&["a", "b"]);
+        let new = lines_of(&["a", "", "b"]);
+
+        let diff = Diff::new(old.clone(), new.clone()).with_ignore_blank_lines(true);
+        assert!(diff.lines_structured().iter().all(|line| !line.is_change()));
+
+        let diff = Diff::new(old, new);
+        assert!(diff.lines_structured().iter().any(|line| line.is_change()));
+    }
+
+    #[test]
+    fn ignore_patterns_match_lines_on_both_sides() {
+        let old = lines_of(&["// generated at 10:00", "a", "b"]);
+        let new = lines_of(&["// generated at 11:30", "a", "c"]);
+        let patterns = IgnorePatterns::new(["^// generated at"]).unwrap();
+
+        let lines = Diff::new(old.clone(), new.clone())
+            .with_ignore_patterns(patterns)
+            .structured_lines();
+        assert!(!lines[0].is_change());
+        assert_eq!(
+            lines[0].new.as_ref().unwrap().content,
+            "// generated at 11:30"
+        );
+        assert!(lines[2].is_change());
+
+        let lines = Diff::new(old, new).structured_lines();
+        assert!(lines[0].is_change());
+    }
+
+    #[test]
+    fn ignore_cr_at_eol_matches_crlf_and_lf_lines() {
+        let old = lines_of(&["a\r", "b\r"]);
+        let new = lines_of(&["a", "c"]);
+
+        let lines = Diff::new(old.clone(), new.clone())
+            .with_ignore_cr_at_eol(true)
+            .structured_lines();
+        assert!(!lines[0].is_change());
+        assert_eq!(lines[0].old.as_ref().unwrap().content, "a\r");
+        assert!(lines[1].is_change());
+
+        let lines = Diff::new(old, new).structured_lines();
+        assert!(lines[0].is_change());
+    }
+
+    #[test]
+    fn replaced_block_pairs_lines_by_similarity() {
+        let old = lines_of(&["start", "let a = compute(1);", "let b = compute(2);"]);
+        let new = lines_of(&[
+            "start",
+            "let a = compute(10);",
+            "println!(\"checkpoint\");",
+            "let b = compute(20);",
+        ]);
+
+        let kinds: Vec<_> = Diff::new(old, new)
+            .structured_lines()
+            .iter()
+            .map(|line| {
+                (
+                    line.old.as_ref().map(|l| (l.lineno, l.diff_type)),
+                    line.new.as_ref().map(|l| (l.lineno, l.diff_type)),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            kinds[1..],
+            [
+                (Some((2, DiffType::Replaced)), Some((2, DiffType::Replaced))),
+                (None, Some((3, DiffType::Added))),
+                (Some((3, DiffType::Replaced)), Some((4, DiffType::Replaced))),
+            ]
+        );
+    }
+
+    #[test]
+    fn context_limits_structured_lines() {
+        let old = lines_of(&["1", "2", "3", "value = 4", "5", "6", "7"]);
+        let new = lines_of(&["1", "2", "3", "value = 40", "5", "6", "7"]);
+
+        let diff = Diff::new(old.clone(), new.clone()).with_context(ContextSize::Lines(1));
+        assert_eq!(diff.lines_structured().len(), 3);
+
+        let diff = Diff::new(old, new).with_context(ContextSize::Full);
+        assert_eq!(diff.lines_structured().len(), 7);
+        assert_eq!(diff.hunks().len(), 1);
+    }
+
+    #[test]
+    fn context_filtering_keeps_same_rows_as_full_file() {
+        let old = lines_of(&["a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k"]);
+        let new = lines_of(&["a", "B", "c", "d", "e", "", "f", "g", "h", "j", "k", "l"]);
+        let numbers = |rows: &[LineDiff]| -> Vec<(Option<usize>, Option<usize>)> {
+            rows.iter()
+                .map(|row| {
+                    (
+                        row.old.as_ref().map(|l| l.lineno),
+                        row.new.as_ref().map(|l| l.lineno),
+                    )
+                })
+                .collect()
+        };
+
+        for ignore_blank_lines in [false, true] {
+            let diff =
+                Diff::new(old.clone(), new.clone()).with_ignore_blank_lines(ignore_blank_lines);
+            let full = diff.structured_lines();
+
+            for context in 0..4 {
+                // 変更行から context 行以内の行だけを残す
+                let expected: Vec<_> = full
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| {
+                        let lo = i.saturating_sub(context);
+                        let hi = (i + context + 1).min(full.len());
+                        full[lo..hi].iter().any(|row| row.is_change())
+                    })
+                    .map(|(_, row)| {
+                        (
+                            row.old.as_ref().map(|l| l.lineno),
+                            row.new.as_ref().map(|l| l.lineno),
+                        )
+                    })
+                    .collect();
+
+                assert_eq!(
+                    numbers(&diff.lines_structured_with_context(context)),
+                    expected,
+                    "context {context}, ignore_blank_lines {ignore_blank_lines}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lines_in_hunks_prefixes_each_hunk_with_header() {
+        let old = lines_of(&["a", "b", "c", "d", "e", "f", "g", "h", "i"]);
+        let new = lines_of(&["a", "B", "c", "d", "e", "f", "g", "H", "i"]);
+
+        let output = Diff::new(old, new)
+            .with_context(ContextSize::Lines(1))
+            .lines_in_hunks(10, 20);
+
+        assert!(output.contains("@@ -10,3 +20,3 @@"));
+        assert!(output.contains("@@ -16,3 +26,3 @@"));
     }
 }
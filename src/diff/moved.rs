@@ -0,0 +1,244 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::{DiffType, LineDiff, LineInfo};
+
+/// これより英数字が少ないまとまりは偶然の一致とみなし、移動扱いにしない（git と同じ基準）
+const MIN_ALNUM_CHARS: usize = 20;
+
+/// 追加側にこれより多く現れる行は、どこへ移ったか決められないため移動の起点にしない
+const MAX_CANDIDATES: usize = 32;
+
+/// 削除側と追加側に同じ内容の連続した行があれば、移動したまとまりとして印を付ける。
+/// インデントや空白量の違いは無視して比較する。
+pub fn mark_moved_blocks(raw: &mut [LineDiff]) {
+    let mut old_lines: BTreeMap<usize, (usize, String)> = BTreeMap::new();
+    let mut new_lines: HashMap<usize, (usize, String)> = HashMap::new();
+    let mut new_by_key: HashMap<String, Vec<usize>> = HashMap::new();
+
+    for (idx, line) in raw.iter().enumerate() {
+        if let Some(old) = line.old.as_ref().filter(|l| is_candidate(l)) {
+            old_lines.insert(old.lineno, (idx, normalize(&old.content)));
+        }
+        if let Some(new) = line.new.as_ref().filter(|l| is_candidate(l)) {
+            let key = normalize(&new.content);
+            if is_anchor(&key) {
+                new_by_key.entry(key.clone()).or_default().push(new.lineno);
+            }
+            new_lines.insert(new.lineno, (idx, key));
+        }
+    }
+
+    let mut used_new: HashSet<usize> = HashSet::new();
+    let mut skip_until = 0;
+    let mut move_id = 0;
+
+    for (&old_lineno, (_, key)) in &old_lines {
+        if old_lineno < skip_until {
+            continue;
+        }
+        let Some(starts) = new_by_key
+            .get(key)
+            .filter(|starts| starts.len() <= MAX_CANDIDATES)
+        else {
+            continue;
+        };
+
+        let mut best: Option<(usize, usize)> = None;
+        for &new_start in starts {
+            let mut len = 0;
+            while let (Some((_, old_key)), Some((_, new_key))) = (
+                old_lines.get(&(old_lineno + len)),
+                new_lines.get(&(new_start + len)),
+            ) {
+                if old_key != new_key || used_new.contains(&(new_start + len)) {
+                    break;
+                }
+                len += 1;
+            }
+            if best.is_none_or(|(_, best_len)| len > best_len) {
+                best = Some((new_start, len));
+            }
+        }
+
+        let Some((new_start, len)) = best else {
+            continue;
+        };
+        let alnum: usize = (0..len)
+            .map(|k| {
+                old_lines[&(old_lineno + k)]
+                    .1
+                    .chars()
+                    .filter(|c| c.is_alphanumeric())
+                    .count()
+            })
+            .sum();
+        if alnum < MIN_ALNUM_CHARS {
+            continue;
+        }
+
+        move_id += 1;
+        for k in 0..len {
+            let old_idx = old_lines[&(old_lineno + k)].0;
+            let new_idx = new_lines[&(new_start + k)].0;
+            mark(raw[old_idx].old.as_mut(), move_id);
+            mark(raw[new_idx].new.as_mut(), move_id);
+            used_new.insert(new_start + k);
+        }
+        skip_until = old_lineno + len;
+    }
+
+    for row in raw.iter_mut() {
+        unpair_moved(row);
+    }
+}
+
+/// 置換として対になっていた行の片側だけが移動したなら、対を解いて残りを単なる削除・追加にする
+fn unpair_moved(row: &mut LineDiff) {
+    let (Some(old), Some(new)) = (row.old.as_mut(), row.new.as_mut()) else {
+        return;
+    };
+    let partner = match (old.diff_type, new.diff_type) {
+        (DiffType::Moved, DiffType::Replaced) => {
+            new.diff_type = DiffType::Added;
+            new
+        }
+        (DiffType::Replaced, DiffType::Moved) => {
+            old.diff_type = DiffType::Removed;
+            old
+        }
+        _ => return,
+    };
+    partner.spans = None;
+}
+
+fn is_candidate(line: &LineInfo) -> bool {
+    line.diff_type != DiffType::Equal
+}
+
+/// 移動の起点にできる行か。空行や `}` だけの行はまとまりの途中でだけ一致させる
+fn is_anchor(key: &str) -> bool {
+    key.chars().any(|c| c.is_alphanumeric())
+}
+
+fn normalize(content: &str) -> String {
+    content.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn mark(line: Option<&mut LineInfo>, move_id: usize) {
+    if let Some(line) = line {
+        line.diff_type = DiffType::Moved;
+        line.spans = None;
+        line.move_id = Some(move_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn removed(lineno: usize, content: &str) -> LineDiff {
+        LineDiff {
+            old: Some(LineInfo::new(lineno, content, DiffType::Removed)),
+            new: None,
+        }
+    }
+
+    fn added(lineno: usize, content: &str) -> LineDiff {
+        LineDiff {
+            old: None,
+            new: Some(LineInfo::new(lineno, content, DiffType::Added)),
+        }
+    }
+
+    fn move_ids(raw: &[LineDiff]) -> Vec<Option<usize>> {
+        raw.iter()
+            .flat_map(|line| [&line.old, &line.new])
+            .flatten()
+            .map(|line| line.move_id)
+            .collect()
+    }
+
+    #[test]
+    fn links_block_moved_with_reindent() {
+        let mut raw = vec![
+            removed(1, "fn helper(value: usize) {"),
+            removed(2, "    println!(\"{}\", value);"),
+            removed(3, "}"),
+            added(1, "    fn helper(value: usize) {"),
+            added(2, "        println!(\"{}\", value);"),
+            added(3, "    }"),
+        ];
+
+        mark_moved_blocks(&mut raw);
+
+        assert_eq!(move_ids(&raw), vec![Some(1); 6]);
+        assert!(
+            raw.iter()
+                .flat_map(|line| [&line.old, &line.new])
+                .flatten()
+                .all(|line| line.diff_type == DiffType::Moved)
+        );
+    }
+
+    #[test]
+    fn ignores_short_coincidental_matches() {
+        let mut raw = vec![removed(1, "}"), removed(2, ""), added(1, "}"), added(2, "")];
+
+        mark_moved_blocks(&mut raw);
+
+        assert_eq!(move_ids(&raw), vec![None; 4]);
+    }
+
+    #[test]
+    fn each_destination_is_used_once() {
+        let line = "let configuration = load_configuration();";
+        let mut raw = vec![removed(1, line), removed(5, line), added(3, line)];
+
+        mark_moved_blocks(&mut raw);
+
+        assert_eq!(move_ids(&raw), vec![Some(1), None, Some(1)]);
+    }
+
+    #[test]
+    fn common_lines_do_not_start_a_block() {
+        let line = "let configuration = load_configuration();";
+        let mut raw: Vec<LineDiff> = (1..=MAX_CANDIDATES + 1)
+            .flat_map(|lineno| [removed(lineno, line), added(lineno, line)])
+            .collect();
+        raw.extend(
+            (1..=5000).flat_map(|lineno| [removed(100 + lineno, "}"), added(100 + lineno, "}")]),
+        );
+
+        mark_moved_blocks(&mut raw);
+
+        assert!(move_ids(&raw).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn moved_line_splits_its_replaced_pair() {
+        use crate::diff::DiffStats;
+
+        let line = "let configuration = load_configuration();";
+        let mut raw = vec![
+            LineDiff {
+                old: Some(LineInfo::new(1, line, DiffType::Replaced)),
+                new: Some(LineInfo {
+                    spans: Some(vec![]),
+                    ..LineInfo::new(1, "let config = other();", DiffType::Replaced)
+                }),
+            },
+            added(7, line),
+        ];
+
+        mark_moved_blocks(&mut raw);
+
+        let old = raw[0].old.as_ref().unwrap();
+        let new = raw[0].new.as_ref().unwrap();
+        assert_eq!((old.diff_type, old.move_id), (DiffType::Moved, Some(1)));
+        assert_eq!((new.diff_type, new.move_id), (DiffType::Added, None));
+        assert_eq!(new.spans, None);
+
+        let stats = DiffStats::from_rows(&raw, 3);
+        assert_eq!((stats.added, stats.removed, stats.replaced), (2, 1, 0));
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+/// これ未満の類似度の行は置換ではなく削除・追加として扱う
+const MIN_SIMILARITY: f64 = 0.5;
+
+/// 置換ブロックがこれより大きい場合は、計算量を抑えるため位置で対応付ける
+const MAX_CELLS: usize = 250_000;
+
+/// 置換ブロック内の old / new の行を内容の近さで対応付ける。
+/// 順序を保ったまま類似度の合計が最大になる組を選び、相手のない行は片側だけで返す
+pub fn pair_lines(old: &[String], new: &[String]) -> Vec<(Option<usize>, Option<usize>)> {
+    if old.len().saturating_mul(new.len()) > MAX_CELLS {
+        return pair_by_index(old.len(), new.len());
+    }
+
+    let old_grams: Vec<_> = old.iter().map(|line| bigrams(line)).collect();
+    let new_grams: Vec<_> = new.iter().map(|line| bigrams(line)).collect();
+
+    let width = new.len() + 1;
+    let mut score = vec![0.0f64; (old.len() + 1) * width];
+    let mut similar = vec![None; old.len() * new.len()];
+
+    for i in 0..old.len() {
+        for j in 0..new.len() {
+            let similarity = dice(&old_grams[i], &new_grams[j]);
+            let mut best = score[i * width + j + 1].max(score[(i + 1) * width + j]);
+            if similarity >= MIN_SIMILARITY {
+                similar[i * new.len() + j] = Some(similarity);
+                best = best.max(score[i * width + j] + similarity);
+            }
+            score[(i + 1) * width + j + 1] = best;
+        }
+    }
+
+    // 末尾から辿り、対応付けた行の間にある片側だけの行は削除 → 追加の順に並べる
+    let mut pairs = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    let (mut i, mut j) = (old.len(), new.len());
+    while i > 0 || j > 0 {
+        let current = score[i * width + j];
+        let paired = (i > 0 && j > 0)
+            .then(|| similar[(i - 1) * new.len() + j - 1])
+            .flatten()
+            .filter(|similarity| current == score[(i - 1) * width + j - 1] + similarity);
+
+        if paired.is_some() {
+            flush(&mut pairs, &mut removed, &mut added);
+            pairs.push((Some(i - 1), Some(j - 1)));
+            i -= 1;
+            j -= 1;
+        } else if j > 0 && (i == 0 || current == score[i * width + j - 1]) {
+            added.push((None, Some(j - 1)));
+            j -= 1;
+        } else {
+            removed.push((Some(i - 1), None));
+            i -= 1;
+        }
+    }
+    flush(&mut pairs, &mut removed, &mut added);
+
+    pairs.reverse();
+    pairs
+}
+
+/// 逆順に積んでいるため、追加 → 削除の順に積むと最終的に削除 → 追加になる
+fn flush<T>(pairs: &mut Vec<T>, removed: &mut Vec<T>, added: &mut Vec<T>) {
+    pairs.append(added);
+    pairs.append(removed);
+}
+
+fn pair_by_index(old_len: usize, new_len: usize) -> Vec<(Option<usize>, Option<usize>)> {
+    (0..old_len.max(new_len))
+        .map(|i| ((i < old_len).then_some(i), (i < new_len).then_some(i)))
+        .collect()
+}
+
+/// インデントの違いに左右されないよう、前後の空白を除いた文字の 2-gram を数える。
+/// 1 文字の行同士も比べられるよう、行頭と行末に番兵を置く
+fn bigrams(line: &str) -> HashMap<(char, char), usize> {
+    let chars: Vec<char> = std::iter::once('\0')
+        .chain(line.trim().chars())
+        .chain(std::iter::once('\0'))
+        .collect();
+    let mut grams = HashMap::new();
+    for pair in chars.windows(2) {
+        *grams.entry((pair[0], pair[1])).or_insert(0) += 1;
+    }
+    grams
+}
+
+/// Sørensen–Dice 係数 (0.0 〜 1.0)
+fn dice(a: &HashMap<(char, char), usize>, b: &HashMap<(char, char), usize>) -> f64 {
+    let total: usize = a.values().sum::<usize>() + b.values().sum::<usize>();
+    let shared: usize = a
+        .iter()
+        .map(|(gram, count)| (*count).min(*b.get(gram).unwrap_or(&0)))
+        .sum();
+    2.0 * shared as f64 / total as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_support::lines_of;
+
+    #[test]
+    fn inserted_line_does_not_shift_following_pairs() {
+        let old = lines_of(&["let a = compute(1);", "let b = compute(2);"]);
+        let new = lines_of(&[
+            "let a = compute(10);",
+            "log::debug!(\"start\");",
+            "let b = compute(20);",
+        ]);
+
+        assert_eq!(
+            pair_lines(&old, &new),
+            vec![(Some(0), Some(0)), (None, Some(1)), (Some(1), Some(2))]
+        );
+    }
+
+    #[test]
+    fn dissimilar_lines_are_not_paired() {
+        let old = lines_of(&["fn parse(input: &str) {"]);
+        let new = lines_of(&["// TODO"]);
+
+        assert_eq!(
+            pair_lines(&old, &new),
+            vec![(Some(0), None), (None, Some(0))]
+        );
+    }
+
+    #[test]
+    fn unpaired_lines_between_pairs_list_removed_first() {
+        let old = lines_of(&["value = 1", "zzzzzzzz", "other = 2"]);
+        let new = lines_of(&["value = 10", "yyyyyyyy", "other = 20"]);
+
+        assert_eq!(
+            pair_lines(&old, &new),
+            vec![
+                (Some(0), Some(0)),
+                (Some(1), None),
+                (None, Some(1)),
+                (Some(2), Some(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn similarity_ignores_indentation() {
+        assert_eq!(dice(&bigrams("    run();"), &bigrams("run();")), 1.0);
+        assert_eq!(dice(&bigrams("b"), &bigrams("c")), 0.0);
+    }
+}
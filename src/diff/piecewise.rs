@@ -0,0 +1,136 @@
+use super::{Diff, DiffProvider, DiffStats, Hunk, PatchTarget, html, unified};
+
+/// 離れた範囲の 1 つ分の差分。`old_start` / `new_start` は範囲の先頭行番号 (1 始まり)
+pub struct DiffPiece {
+    pub diff: Diff,
+    pub old_start: usize,
+    pub new_start: usize,
+}
+
+/// 離れた複数の範囲を、範囲ごとに別々に比較した差分。
+/// 範囲の境目は行として持たないため、別の範囲の行同士が対応付けられることはない
+pub struct PiecewiseDiff {
+    pieces: Vec<DiffPiece>,
+}
+
+impl PiecewiseDiff {
+    pub fn new(pieces: Vec<DiffPiece>) -> Self {
+        Self { pieces }
+    }
+
+    pub fn pieces(&self) -> &[DiffPiece] {
+        &self.pieces
+    }
+
+    /// 範囲ごとのハンクを、ファイル全体での行番号にして順に並べる
+    pub fn hunks(&self) -> Vec<Hunk> {
+        self.pieces
+            .iter()
+            .flat_map(|piece| {
+                piece
+                    .diff
+                    .hunks()
+                    .into_iter()
+                    .map(|hunk| hunk.at(piece.old_start, piece.new_start))
+            })
+            .collect()
+    }
+
+    /// すべての範囲を合わせた、追加・削除・置換された行数と一致率
+    pub fn stats(&self) -> DiffStats {
+        let mut rows = Vec::new();
+        let mut total = 0;
+        for piece in &self.pieces {
+            rows.extend(piece.diff.collect_rows(0).0);
+            total += piece.diff.old.len() + piece.diff.new.len();
+        }
+        DiffStats::from_rows(&rows, total)
+    }
+
+    /// unified diff 形式のパッチ。ハンクの行番号は範囲ごとのファイル全体での行番号になる
+    pub fn unified(&self, old_path: &str, new_path: &str) -> String {
+        let (old, new) = targets(old_path, new_path);
+        unified::render(&self.hunks(), &old, &new)
+    }
+
+    /// 行番号と構文の色付きの表にした HTML
+    pub fn html(&self, old_path: &str, new_path: &str) -> String {
+        let (old, new) = targets(old_path, new_path);
+        html::render(&self.hunks(), &old, &new)
+    }
+}
+
+/// ハンクの行番号はずらしてあるため、パッチ側では先頭行を 1 のままにする
+fn targets<'a>(old_path: &'a str, new_path: &'a str) -> (PatchTarget<'a>, PatchTarget<'a>) {
+    (
+        PatchTarget {
+            path: old_path,
+            start: 1,
+        },
+        PatchTarget {
+            path: new_path,
+            start: 1,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::ContextSize;
+    use crate::utils::test_support::lines_of;
+
+    fn piece(old: &[&str], new: &[&str], old_start: usize, new_start: usize) -> DiffPiece {
+        DiffPiece {
+            diff: Diff::new(lines_of(old), lines_of(new)).with_context(ContextSize::Lines(1)),
+            old_start,
+            new_start,
+        }
+    }
+
+    #[test]
+    fn hunks_use_file_line_numbers_of_each_piece() {
+        let diff = PiecewiseDiff::new(vec![
+            piece(&["a", "b"], &["a", "B"], 1, 1),
+            piece(&["x", "y"], &["x", "Y"], 40, 50),
+        ]);
+
+        let hunks = diff.hunks();
+
+        assert_eq!(hunks.len(), 2);
+        assert_eq!((hunks[0].old_start, hunks[0].new_start), (1, 1));
+        assert_eq!((hunks[1].old_start, hunks[1].new_start), (40, 50));
+        assert!(
+            diff.unified("a.rs", "a.rs")
+                .contains("@@ -40,2 +50,2 @@\n x\n-y\n+Y\n")
+        );
+    }
+
+    #[test]
+    fn lines_are_never_paired_across_pieces() {
+        // 2 つ目の範囲の行が 1 つ目の範囲に移ったように見えても、範囲ごとに比較する
+        let diff = PiecewiseDiff::new(vec![
+            piece(&["a"], &["a", "z"], 1, 1),
+            piece(&["z", "b"], &["b"], 10, 20),
+        ]);
+
+        let stats = diff.stats();
+
+        assert_eq!((stats.added, stats.removed), (1, 1));
+        assert_eq!(diff.pieces()[0].diff.stats().removed, 0);
+    }
+
+    #[test]
+    fn separator_like_content_is_an_ordinary_line() {
+        let diff = PiecewiseDiff::new(vec![
+            piece(&["⋯"], &["⋯"], 1, 1),
+            piece(&["a"], &["⋯"], 5, 5),
+        ]);
+
+        let hunks = diff.hunks();
+
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].old_start, 5);
+        assert!(diff.unified("a", "a").contains("-a\n+⋯\n"));
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+/// 探した行の中で、もっとも似ていた箇所
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegionMatch {
+    /// 1 始まり、両端を含む
+    pub start: usize,
+    pub end: usize,
+    /// 0.0 〜 1.0 の類似度
+    pub score: f64,
+}
+
+/// `needle` と同じ行数の窓を `haystack` の先頭からずらし、もっとも似ている窓を返す。
+/// 大きく書き換えられたコードも見つけられるよう、行の対応ではなく、識別子や数値などの
+/// 語の出現数の重なり (Dice 係数) で比べる。語が 1 つも重ならなければ `None`
+pub fn find_region(needle: &[String], haystack: &[String]) -> Option<RegionMatch> {
+    let mut wanted: HashMap<&str, usize> = HashMap::new();
+    for word in needle.iter().flat_map(|line| words(line)) {
+        *wanted.entry(word).or_default() += 1;
+    }
+    let wanted_total: usize = wanted.values().sum();
+    let size = needle.len().min(haystack.len());
+    if wanted_total == 0 || size == 0 {
+        return None;
+    }
+
+    let mut window: HashMap<&str, usize> = HashMap::new();
+    let mut window_total = 0;
+    let mut common = 0;
+    let mut best: Option<RegionMatch> = None;
+
+    for (i, line) in haystack.iter().enumerate() {
+        for word in words(line) {
+            let count = window.entry(word).or_default();
+            if *count < wanted.get(word).copied().unwrap_or(0) {
+                common += 1;
+            }
+            *count += 1;
+            window_total += 1;
+        }
+        if i >= size {
+            for word in words(&haystack[i - size]) {
+                let count = window.entry(word).or_default();
+                *count -= 1;
+                if *count < wanted.get(word).copied().unwrap_or(0) {
+                    common -= 1;
+                }
+                window_total -= 1;
+            }
+        }
+        if i + 1 < size {
+            continue;
+        }
+
+        let score = 2.0 * common as f64 / (wanted_total + window_total) as f64;
+        if common > 0 && best.as_ref().is_none_or(|best| score > best.score) {
+            best = Some(RegionMatch {
+                start: i + 2 - size,
+                end: i + 1,
+                score,
+            });
+        }
+    }
+
+    best
+}
+
+/// 英数字と `_` の並びを 1 語とする。記号や空白、インデントの違いは比べない。
+/// 前後の空白を除いた行全体も 1 語に数え、`}` だけの行や空行の有無も窓の位置に効くようにする
+fn words(line: &str) -> impl Iterator<Item = &str> {
+    line.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .chain(std::iter::once(line.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_support::lines_of;
+
+    #[test]
+    fn finds_moved_and_edited_block() {
+        let needle = lines_of(&[
+            "fn total(items: &[Item]) -> u32 {",
+            "    items.iter().map(|i| i.price).sum()",
+            "}",
+        ]);
+        let haystack = lines_of(&[
+            "use crate::Item;",
+            "",
+            "fn count(items: &[Item]) -> usize {",
+            "    items.len()",
+            "}",
+            "",
+            "pub fn total(items: &[Item]) -> u64 {",
+            "    items.iter().map(|item| item.price as u64).sum()",
+            "}",
+        ]);
+
+        let found = find_region(&needle, &haystack).unwrap();
+
+        assert_eq!((found.start, found.end), (7, 9));
+        assert!(found.score > 0.5 && found.score < 1.0);
+    }
+
+    #[test]
+    fn exact_copy_scores_one() {
+        let needle = lines_of(&["let a = 1;", "let b = 2;"]);
+        let haystack = lines_of(&["x", "let a = 1;", "let b = 2;", "y"]);
+
+        let found = find_region(&needle, &haystack).unwrap();
+
+        assert_eq!((found.start, found.end, found.score), (2, 3, 1.0));
+    }
+
+    #[test]
+    fn window_is_clamped_to_short_haystack() {
+        let needle = lines_of(&["a", "b", "c"]);
+        let haystack = lines_of(&["b"]);
+
+        let found = find_region(&needle, &haystack).unwrap();
+
+        assert_eq!((found.start, found.end), (1, 1));
+    }
+
+    #[test]
+    fn no_common_words_finds_nothing() {
+        let needle = lines_of(&["alpha beta"]);
+        let haystack = lines_of(&["gamma", "delta"]);
+
+        assert_eq!(find_region(&needle, &haystack), None);
+        assert_eq!(find_region(&lines_of(&["{", "}"]), &haystack), None);
+    }
+}
@@ -0,0 +1,190 @@
+use prettydiff::owo_colors::OwoColorize;
+use unicode_width::UnicodeWidthChar;
+
+use super::{DiffType, LineDiff, LineInfo};
+
+pub(super) const SEPARATOR: &str = " │ ";
+pub(super) const MIN_COLUMN_WIDTH: usize = 10;
+const TAB_WIDTH: usize = 4;
+
+/// 表示幅に収まらない行の扱い
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Overflow {
+    Truncate,
+    Wrap,
+}
+
+/// 行番号付きの 2 列で差分を描画する。`width` は端末の表示幅
+pub fn render(rows: &[LineDiff], width: usize, overflow: Overflow) -> String {
+    let max_lineno = rows
+        .iter()
+        .flat_map(|row| [&row.old, &row.new])
+        .flatten()
+        .map(|line| line.lineno)
+        .max()
+        .unwrap_or(0);
+    let number_width = max_lineno.to_string().len();
+    let column_width =
+        (width.saturating_sub(SEPARATOR.len() + 2 * (number_width + 1)) / 2).max(MIN_COLUMN_WIDTH);
+
+    let mut out = Vec::new();
+    let mut last_old: Option<usize> = None;
+    let mut last_new: Option<usize> = None;
+
+    for row in rows {
+        let old_lineno = row.old.as_ref().map(|l| l.lineno);
+        let new_lineno = row.new.as_ref().map(|l| l.lineno);
+
+        // 直前の行から行番号が飛んでいれば、文脈の切れ目として区切り線を入れる
+        let is_gap = |last: Option<usize>, current: Option<usize>| matches!((last, current), (Some(last), Some(current)) if current != last + 1);
+        if is_gap(last_old, old_lineno) || is_gap(last_new, new_lineno) {
+            let rule = "┈".repeat(2 * (number_width + 1 + column_width) + SEPARATOR.len());
+            out.push(rule.dimmed().to_string());
+        }
+        last_old = old_lineno.or(last_old);
+        last_new = new_lineno.or(last_new);
+
+        let mut old_cells = cell_lines(row.old.as_ref(), column_width, overflow);
+        let mut new_cells = cell_lines(row.new.as_ref(), column_width, overflow);
+        let height = old_cells.len().max(new_cells.len());
+        old_cells.resize(height, " ".repeat(column_width));
+        new_cells.resize(height, " ".repeat(column_width));
+
+        for (k, (old_cell, new_cell)) in old_cells.iter().zip(&new_cells).enumerate() {
+            let old = side(row.old.as_ref(), old_cell, k == 0, number_width, true);
+            let new = side(row.new.as_ref(), new_cell, k == 0, number_width, false);
+            out.push(format!("{}{}{}", old, SEPARATOR.dimmed(), new));
+        }
+    }
+
+    out.join("\n")
+}
+
+/// セルの中身を表示幅ごとに分割し、各片を幅いっぱいまで空白で埋める
+pub(super) fn cell_lines(line: Option<&LineInfo>, width: usize, overflow: Overflow) -> Vec<String> {
+    let Some(line) = line else {
+        return vec![" ".repeat(width)];
+    };
+    // CRLF の `\r` をそのまま出すと行頭に戻ってしまうため、見える記号にする
+    let content = line
+        .content
+        .replace('\t', &" ".repeat(TAB_WIDTH))
+        .replace('\r', "␍");
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_width = 0;
+
+    for c in content.chars() {
+        let w = c.width().unwrap_or(0);
+        if current_width + w > width {
+            match overflow {
+                Overflow::Truncate => {
+                    // 末尾に省略記号を置けるよう、はみ出す分だけ削る
+                    while current_width + 1 > width {
+                        let Some(removed) = current.pop() else {
+                            break;
+                        };
+                        current_width -= removed.width().unwrap_or(0);
+                    }
+                    current.push('…');
+                    current_width += 1;
+                    break;
+                }
+                Overflow::Wrap => {
+                    chunks.push(pad(std::mem::take(&mut current), current_width, width));
+                    current_width = 0;
+                }
+            }
+        }
+        current.push(c);
+        current_width += w;
+    }
+    chunks.push(pad(current, current_width, width));
+    chunks
+}
+
+fn pad(mut text: String, text_width: usize, width: usize) -> String {
+    text.push_str(&" ".repeat(width.saturating_sub(text_width)));
+    text
+}
+
+pub(super) fn side(
+    line: Option<&LineInfo>,
+    cell: &str,
+    is_first: bool,
+    number_width: usize,
+    is_old: bool,
+) -> String {
+    let number = match line {
+        Some(line) if is_first => format!("{:>number_width$}", line.lineno),
+        _ => " ".repeat(number_width),
+    };
+
+    let text = match line.map(|l| l.diff_type) {
+        Some(DiffType::Added) => cell.green().to_string(),
+        Some(DiffType::Removed) => cell.red().to_string(),
+        Some(DiffType::Replaced) => cell.yellow().to_string(),
+        Some(DiffType::Moved) if is_old => cell.magenta().to_string(),
+        Some(DiffType::Moved) => cell.cyan().to_string(),
+        Some(DiffType::Equal) | None => cell.to_string(),
+    };
+
+    format!("{} {}", number.dimmed(), text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(lineno: usize, content: &str, diff_type: DiffType) -> LineInfo {
+        LineInfo::new(lineno, content, diff_type)
+    }
+
+    fn widths(cells: &[String]) -> Vec<usize> {
+        cells
+            .iter()
+            .map(|cell| cell.chars().map(|c| c.width().unwrap_or(0)).sum())
+            .collect()
+    }
+
+    #[test]
+    fn pads_wide_characters_by_display_width() {
+        let line = info(1, "日本語", DiffType::Equal);
+        let cells = cell_lines(Some(&line), 10, Overflow::Truncate);
+        assert_eq!(cells, vec!["日本語    ".to_string()]);
+        assert_eq!(widths(&cells), vec![10]);
+    }
+
+    #[test]
+    fn truncates_with_ellipsis() {
+        let line = info(1, "あいうえお", DiffType::Equal);
+        let cells = cell_lines(Some(&line), 6, Overflow::Truncate);
+        assert_eq!(cells, vec!["あい… ".to_string()]);
+        assert_eq!(widths(&cells), vec![6]);
+    }
+
+    #[test]
+    fn wraps_without_splitting_wide_characters() {
+        let line = info(1, "abあいう", DiffType::Equal);
+        let cells = cell_lines(Some(&line), 5, Overflow::Wrap);
+        assert_eq!(cells, vec!["abあ ".to_string(), "いう ".to_string()]);
+    }
+
+    #[test]
+    fn inserts_rule_between_hunks() {
+        let rows = vec![
+            LineDiff {
+                old: Some(info(1, "a", DiffType::Removed)),
+                new: None,
+            },
+            LineDiff {
+                old: Some(info(9, "b", DiffType::Equal)),
+                new: Some(info(8, "b", DiffType::Equal)),
+            },
+        ];
+        let rendered = render(&rows, 40, Overflow::Truncate);
+        assert_eq!(rendered.lines().count(), 3);
+        assert!(rendered.lines().nth(1).unwrap().contains('┈'));
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+
+use super::{DiffType, LineDiff};
+
+/// 差分の大きさ。`similarity` は変更されなかった行の割合 (0.0 〜 1.0)
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct DiffStats {
+    pub added: usize,
+    pub removed: usize,
+    pub replaced: usize,
+    pub similarity: f64,
+}
+
+impl DiffStats {
+    /// 変更行から集計する。`total` は old / new の行数の合計で、移動した行は削除・追加の両方に数える
+    pub(super) fn from_rows(rows: &[LineDiff], total: usize) -> Self {
+        let mut stats = DiffStats {
+            added: 0,
+            removed: 0,
+            replaced: 0,
+            similarity: 1.0,
+        };
+        let mut changed = 0;
+
+        for row in rows {
+            let old_type = row.old.as_ref().map(|line| line.diff_type);
+            let new_type = row.new.as_ref().map(|line| line.diff_type);
+
+            match (old_type, new_type) {
+                (Some(DiffType::Replaced), Some(DiffType::Replaced)) => stats.replaced += 1,
+                (old_type, new_type) => {
+                    if matches!(old_type, Some(DiffType::Removed | DiffType::Moved)) {
+                        stats.removed += 1;
+                    }
+                    if matches!(new_type, Some(DiffType::Added | DiffType::Moved)) {
+                        stats.added += 1;
+                    }
+                }
+            }
+
+            changed += [old_type, new_type]
+                .into_iter()
+                .flatten()
+                .filter(|diff_type| *diff_type != DiffType::Equal)
+                .count();
+        }
+
+        if total > 0 {
+            stats.similarity = (total - changed) as f64 / total as f64;
+        }
+        stats
+    }
+}
+
+impl fmt::Display for DiffStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "+{} -{} ~{} (一致率 {:.0}%)",
+            self.added,
+            self.removed,
+            self.replaced,
+            self.similarity * 100.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff::Diff;
+    use crate::utils::test_support::lines_of;
+
+    #[test]
+    fn counts_each_kind_of_change() {
+        let old = lines_of(&["a", "let x = 1;", "b", "gone"]);
+        let new = lines_of(&["a", "let x = 2;", "b", "new line", "another"]);
+
+        let stats = Diff::new(old, new).stats();
+
+        assert_eq!((stats.added, stats.removed, stats.replaced), (2, 1, 1));
+        assert_eq!(stats.similarity, 4.0 / 9.0);
+    }
+
+    #[test]
+    fn identical_files_are_fully_similar() {
+        let stats = Diff::new(lines_of(&["a"]), lines_of(&["a"])).stats();
+        assert_eq!((stats.added, stats.removed, stats.replaced), (0, 0, 0));
+        assert_eq!(stats.similarity, 1.0);
+
+        let stats = Diff::new(vec![], vec![]).stats();
+        assert_eq!(stats.similarity, 1.0);
+    }
+
+    #[test]
+    fn display_is_compact() {
+        let stats = Diff::new(lines_of(&["a", "b"]), lines_of(&["a", "c", "d"])).stats();
+        assert_eq!(stats.to_string(), "+2 -1 ~0 (一致率 40%)");
+    }
+}
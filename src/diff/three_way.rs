@@ -0,0 +1,384 @@
+use std::ops::Range;
+
+use prettydiff::owo_colors::OwoColorize;
+
+use super::side_by_side::{self, MIN_COLUMN_WIDTH, Overflow, SEPARATOR};
+use super::{ContextSize, Diff, DiffType, LineInfo, LineOp};
+
+/// 変更がどちらのブランチで行われたか
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeOrigin {
+    Unchanged,
+    /// ベースブランチだけが変更した
+    Base,
+    /// 比較対象のブランチだけが変更した
+    Target,
+    /// 両方のブランチが同じ変更をした
+    Both,
+    /// 両方のブランチが異なる変更をした
+    Conflict,
+}
+
+impl ChangeOrigin {
+    fn marker(&self) -> char {
+        match self {
+            ChangeOrigin::Unchanged => ' ',
+            ChangeOrigin::Base => 'B',
+            ChangeOrigin::Target => 'T',
+            ChangeOrigin::Both => '=',
+            ChangeOrigin::Conflict => '!',
+        }
+    }
+}
+
+/// マージベース・ベースブランチ・比較対象の 3 列の 1 行分
+#[derive(serde::Serialize)]
+pub struct ThreeWayLine {
+    pub merge_base: Option<LineInfo>,
+    pub base: Option<LineInfo>,
+    pub target: Option<LineInfo>,
+    pub origin: ChangeOrigin,
+}
+
+impl ThreeWayLine {
+    pub fn is_change(&self) -> bool {
+        self.origin != ChangeOrigin::Unchanged
+    }
+}
+
+/// マージベースからベースブランチ・比較対象それぞれへの差分を突き合わせた 3 方向の比較。
+/// どちらの差分も old 側はマージベースの行
+pub struct ThreeWayDiff {
+    base: Diff,
+    target: Diff,
+    context: ContextSize,
+}
+
+impl ThreeWayDiff {
+    pub(super) fn new(base: Diff, target: Diff, context: ContextSize) -> Self {
+        Self {
+            base,
+            target,
+            context,
+        }
+    }
+
+    /// 変更行とその前後の文脈だけを返す
+    pub fn lines_structured(&self) -> Vec<ThreeWayLine> {
+        let rows = self.rows();
+        let context = self.context.lines();
+
+        // 前後それぞれから、最も近い変更行までの距離を測る
+        let mut keep = vec![false; rows.len()];
+        let mut distance = usize::MAX;
+        for (i, row) in rows.iter().enumerate() {
+            distance = if row.is_change() {
+                0
+            } else {
+                distance.saturating_add(1)
+            };
+            keep[i] = distance <= context;
+        }
+        distance = usize::MAX;
+        for (i, row) in rows.iter().enumerate().rev() {
+            distance = if row.is_change() {
+                0
+            } else {
+                distance.saturating_add(1)
+            };
+            keep[i] |= distance <= context;
+        }
+
+        rows.into_iter()
+            .zip(keep)
+            .filter_map(|(row, keep)| keep.then_some(row))
+            .collect()
+    }
+
+    /// 行番号付きの 3 列で描画する。行頭の記号は変更元 (B: ベース, T: 対象, =: 両方, !: 衝突)
+    pub fn side_by_side(&self, width: usize, overflow: Overflow) -> String {
+        let rows = self.lines_structured();
+        let max_lineno = rows
+            .iter()
+            .flat_map(|row| [&row.merge_base, &row.base, &row.target])
+            .flatten()
+            .map(|line| line.lineno)
+            .max()
+            .unwrap_or(0);
+        let number_width = max_lineno.to_string().len();
+        let column_width = (width.saturating_sub(2 + 2 * SEPARATOR.len() + 3 * (number_width + 1))
+            / 3)
+        .max(MIN_COLUMN_WIDTH);
+
+        let title = |name: &str| {
+            let name: String = name.chars().take(column_width).collect();
+            format!("{:width$}", name, width = number_width + 1 + column_width)
+        };
+        let mut out = vec![
+            format!(
+                "  {}{}{}{}{}",
+                title("merge base"),
+                SEPARATOR,
+                title("base"),
+                SEPARATOR,
+                title("target")
+            )
+            .dimmed()
+            .to_string(),
+        ];
+
+        let mut last: [Option<usize>; 3] = [None; 3];
+        for row in &rows {
+            let lines = [&row.merge_base, &row.base, &row.target].map(|line| line.as_ref());
+            let linenos = lines.map(|line| line.map(|l| l.lineno));
+
+            // どれかの列で行番号が飛んでいれば、文脈の切れ目として区切り線を入れる
+            let is_gap = last.iter().zip(&linenos).any(|(last, current)| {
+                matches!((last, current), (Some(last), Some(current)) if *current != last + 1)
+            });
+            if is_gap {
+                let rule =
+                    "┈".repeat(2 + 3 * (number_width + 1 + column_width) + 2 * SEPARATOR.len());
+                out.push(rule.dimmed().to_string());
+            }
+            for (last, current) in last.iter_mut().zip(linenos) {
+                *last = current.or(*last);
+            }
+
+            let mut cells =
+                lines.map(|line| side_by_side::cell_lines(line, column_width, overflow));
+            let height = cells.iter().map(Vec::len).max().unwrap_or(1);
+            for column in &mut cells {
+                column.resize(height, " ".repeat(column_width));
+            }
+
+            for k in 0..height {
+                let marker = match (k, row.origin) {
+                    (0, ChangeOrigin::Conflict) => row.origin.marker().red().to_string(),
+                    (0, origin) => origin.marker().to_string(),
+                    _ => " ".to_string(),
+                };
+                let columns: Vec<String> = lines
+                    .iter()
+                    .zip(&cells)
+                    .map(|(line, cell)| {
+                        side_by_side::side(*line, &cell[k], k == 0, number_width, false)
+                    })
+                    .collect();
+                out.push(format!(
+                    "{} {}",
+                    marker,
+                    columns.join(&SEPARATOR.dimmed().to_string())
+                ));
+            }
+        }
+
+        out.join("\n")
+    }
+
+    /// 両側で変更されずに残ったマージベースの行を区切りにして、間の塊ごとに変更元を判定する
+    fn rows(&self) -> Vec<ThreeWayLine> {
+        let merge_base = &self.base.old;
+        let kept_base = kept_lines(&self.base);
+        let kept_target = kept_lines(&self.target);
+
+        let mut rows = Vec::new();
+        let (mut m, mut b, mut t) = (0, 0, 0);
+        for i in 0..merge_base.len() {
+            let (Some(j), Some(k)) = (kept_base[i], kept_target[i]) else {
+                continue;
+            };
+            self.push_chunk(&mut rows, m..i, b..j, t..k, (&kept_base, &kept_target));
+            rows.push(ThreeWayLine {
+                merge_base: Some(LineInfo::new(i + 1, &merge_base[i], DiffType::Equal)),
+                base: Some(LineInfo::new(j + 1, &self.base.new[j], DiffType::Equal)),
+                target: Some(LineInfo::new(k + 1, &self.target.new[k], DiffType::Equal)),
+                origin: ChangeOrigin::Unchanged,
+            });
+            (m, b, t) = (i + 1, j + 1, k + 1);
+        }
+        self.push_chunk(
+            &mut rows,
+            m..merge_base.len(),
+            b..self.base.new.len(),
+            t..self.target.new.len(),
+            (&kept_base, &kept_target),
+        );
+
+        rows
+    }
+
+    fn push_chunk(
+        &self,
+        rows: &mut Vec<ThreeWayLine>,
+        merge_base: Range<usize>,
+        base: Range<usize>,
+        target: Range<usize>,
+        (kept_base, kept_target): (&[Option<usize>], &[Option<usize>]),
+    ) {
+        let height = merge_base.len().max(base.len()).max(target.len());
+        if height == 0 {
+            return;
+        }
+
+        let is_unchanged = |kept: &[Option<usize>], side: &Range<usize>| {
+            side.len() == merge_base.len() && merge_base.clone().all(|i| kept[i].is_some())
+        };
+        let base_changed = !is_unchanged(kept_base, &base);
+        let target_changed = !is_unchanged(kept_target, &target);
+        let origin = match (base_changed, target_changed) {
+            (true, false) => ChangeOrigin::Base,
+            (false, true) => ChangeOrigin::Target,
+            (true, true) if self.same_change(&base, &target) => ChangeOrigin::Both,
+            (true, true) => ChangeOrigin::Conflict,
+            (false, false) => ChangeOrigin::Unchanged,
+        };
+
+        let at = |range: &Range<usize>, r: usize| {
+            (range.start + r < range.end).then_some(range.start + r)
+        };
+        for r in 0..height {
+            let m = at(&merge_base, r);
+            let b = at(&base, r);
+            let t = at(&target, r);
+
+            // 塊の中でも、マージベースの行がそのまま残っている行は変更なしとして示す
+            let side_type = |changed: bool, kept: &[Option<usize>], side: usize| match m {
+                _ if !changed => DiffType::Equal,
+                Some(i) if kept[i] == Some(side) => DiffType::Equal,
+                Some(_) => DiffType::Replaced,
+                None => DiffType::Added,
+            };
+            // 変更した側のどれにも対応する行がなければ、マージベースの行は削除されている
+            let removed = (!base_changed || b.is_none()) && (!target_changed || t.is_none());
+            let merge_base_type = if removed {
+                DiffType::Removed
+            } else {
+                DiffType::Replaced
+            };
+
+            rows.push(ThreeWayLine {
+                merge_base: m.map(|i| LineInfo::new(i + 1, &self.base.old[i], merge_base_type)),
+                base: b.map(|j| {
+                    let diff_type = side_type(base_changed, kept_base, j);
+                    LineInfo::new(j + 1, &self.base.new[j], diff_type)
+                }),
+                target: t.map(|k| {
+                    let diff_type = side_type(target_changed, kept_target, k);
+                    LineInfo::new(k + 1, &self.target.new[k], diff_type)
+                }),
+                origin,
+            });
+        }
+    }
+
+    /// 両ブランチの変更後の行が、比較の設定の上で同じか
+    fn same_change(&self, base: &Range<usize>, target: &Range<usize>) -> bool {
+        base.len() == target.len()
+            && base.clone().zip(target.clone()).all(|(j, k)| {
+                self.base.key(&self.base.new[j]) == self.target.key(&self.target.new[k])
+            })
+    }
+}
+
+/// マージベースの各行が、その側で変更されずに残っている位置
+fn kept_lines(diff: &Diff) -> Vec<Option<usize>> {
+    let mut kept = vec![None; diff.old.len()];
+    for op in diff.line_ops() {
+        if let LineOp::Equal(old, new) = op {
+            for (i, j) in old.zip(new) {
+                kept[i] = Some(j);
+            }
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_support::lines_of;
+
+    fn origins(rows: &[ThreeWayLine]) -> Vec<ChangeOrigin> {
+        rows.iter().map(|row| row.origin).collect()
+    }
+
+    #[test]
+    fn marks_which_branch_changed_each_line() {
+        let merge_base = lines_of(&["a", "b", "c", "d"]);
+        let base = lines_of(&["a", "B", "c", "d"]);
+        let target = lines_of(&["a", "b", "c", "D", "e"]);
+
+        let rows = Diff::new(base, target)
+            .with_context(ContextSize::Full)
+            .three_way(merge_base)
+            .lines_structured();
+
+        assert_eq!(
+            origins(&rows),
+            vec![
+                ChangeOrigin::Unchanged,
+                ChangeOrigin::Base,
+                ChangeOrigin::Unchanged,
+                ChangeOrigin::Target,
+                ChangeOrigin::Target,
+            ]
+        );
+        assert_eq!(rows[1].base.as_ref().unwrap().diff_type, DiffType::Replaced);
+        assert_eq!(rows[1].target.as_ref().unwrap().diff_type, DiffType::Equal);
+        assert_eq!(rows[4].merge_base.as_ref().map(|l| l.lineno), None);
+        assert_eq!(rows[4].target.as_ref().unwrap().diff_type, DiffType::Added);
+    }
+
+    #[test]
+    fn distinguishes_same_change_from_conflict() {
+        let merge_base = lines_of(&["a", "b", "c", "d", "e"]);
+        let base = lines_of(&["a", "x", "c", "y", "e"]);
+        let target = lines_of(&["a", "x", "c", "z", "e"]);
+
+        let rows = Diff::new(base, target)
+            .with_context(ContextSize::Lines(0))
+            .three_way(merge_base)
+            .lines_structured();
+
+        assert_eq!(
+            origins(&rows),
+            vec![ChangeOrigin::Both, ChangeOrigin::Conflict]
+        );
+    }
+
+    #[test]
+    fn removed_lines_keep_merge_base_content() {
+        let merge_base = lines_of(&["a", "b", "c"]);
+        let base = lines_of(&["a", "b", "c"]);
+        let target = lines_of(&["a", "c"]);
+
+        let rows = Diff::new(base, target)
+            .with_context(ContextSize::Lines(0))
+            .three_way(merge_base)
+            .lines_structured();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].origin, ChangeOrigin::Target);
+        let merge_base = rows[0].merge_base.as_ref().unwrap();
+        assert_eq!(
+            (merge_base.content.as_str(), merge_base.diff_type),
+            ("b", DiffType::Removed)
+        );
+        assert!(rows[0].target.is_none());
+    }
+
+    #[test]
+    fn side_by_side_prefixes_rows_with_origin() {
+        let rendered = Diff::new(lines_of(&["a", "x"]), lines_of(&["a", "b"]))
+            .three_way(lines_of(&["a", "b"]))
+            .side_by_side(60, Overflow::Truncate);
+
+        let rows: Vec<&str> = rendered.lines().collect();
+        assert_eq!(rows.len(), 3);
+        assert!(rows[0].contains("merge base"));
+        assert!(rows[1].starts_with("  "));
+        assert!(rows[2].starts_with("B "));
+    }
+}
@@ -0,0 +1,181 @@
+use super::{DiffType, Hunk, LineDiff};
+
+/// パッチに書き出す片側のファイル情報。`start` は抽出した範囲の先頭行番号（1 始まり）
+pub struct PatchTarget<'a> {
+    pub path: &'a str,
+    pub start: usize,
+}
+
+/// ハンクから `git apply` / `patch` で適用できる unified diff を組み立てる
+pub fn render(hunks: &[Hunk], old: &PatchTarget, new: &PatchTarget) -> String {
+    if hunks.is_empty() {
+        return String::new();
+    }
+
+    let old_offset = old.start.max(1) - 1;
+    let new_offset = new.start.max(1) - 1;
+
+    let mut out = format!("--- a/{}\n+++ b/{}\n", old.path, new.path);
+    for hunk in hunks {
+        out.push_str(&hunk_header(hunk, old_offset, new_offset));
+        out.push('\n');
+
+        for (tag, content) in patch_lines(&hunk.lines) {
+            out.push(tag);
+            out.push_str(content);
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+/// 行番号を除いたパッチの各行。リベースで位置がずれただけのパッチ同士を比べられるよう、
+/// git range-diff と同じく `@@` には見出しだけを残す
+pub fn render_without_line_numbers(hunks: &[Hunk]) -> Vec<String> {
+    let mut out = Vec::new();
+    for hunk in hunks {
+        match &hunk.header {
+            Some(text) => out.push(format!("@@ {}", text)),
+            None => out.push("@@".to_string()),
+        }
+        out.extend(
+            patch_lines(&hunk.lines)
+                .into_iter()
+                .map(|(tag, content)| format!("{}{}", tag, content)),
+        );
+    }
+    out
+}
+
+/// `@@ -s,l +s,l @@` に関数名などの見出しを続けた行を返す
+pub(super) fn hunk_header(hunk: &Hunk, old_offset: usize, new_offset: usize) -> String {
+    let mut header = format!(
+        "@@ -{} +{} @@",
+        hunk_range(hunk.old_start + old_offset, hunk.old_len),
+        hunk_range(hunk.new_start + new_offset, hunk.new_len)
+    );
+    if let Some(text) = &hunk.header {
+        header.push(' ');
+        header.push_str(text);
+    }
+    header
+}
+
+/// 変更のまとまりごとに削除行 → 追加行の順へ並べ替える
+fn patch_lines(rows: &[LineDiff]) -> Vec<(char, &str)> {
+    let mut lines = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+
+    for row in rows {
+        match (&row.old, &row.new) {
+            (Some(old), Some(new))
+                if old.diff_type == DiffType::Equal && new.diff_type == DiffType::Equal =>
+            {
+                lines.append(&mut removed);
+                lines.append(&mut added);
+                lines.push((' ', old.content.as_str()));
+            }
+            (old, new) => {
+                // 空行の無視などで片側だけの Equal になった行も、パッチでは実際の追加・削除として出力する
+                if let Some(old) = old {
+                    removed.push(('-', old.content.as_str()));
+                }
+                if let Some(new) = new {
+                    added.push(('+', new.content.as_str()));
+                }
+            }
+        }
+    }
+    lines.append(&mut removed);
+    lines.append(&mut added);
+
+    lines
+}
+
+fn hunk_range(start: usize, len: usize) -> String {
+    if len == 1 {
+        start.to_string()
+    } else {
+        format!("{},{}", start, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::{ContextSize, Diff, DiffProvider};
+    use crate::utils::test_support::lines_of;
+
+    fn target(start: usize) -> PatchTarget<'static> {
+        PatchTarget {
+            path: "src/lib.rs",
+            start,
+        }
+    }
+
+    #[test]
+    fn identical_input_produces_empty_patch() {
+        let diff = Diff::new(lines_of(&["a"]), lines_of(&["a"]));
+        assert_eq!(render(&diff.hunks(), &target(1), &target(1)), "");
+    }
+
+    #[test]
+    fn splits_distant_changes_into_hunks() {
+        let old: Vec<String> = (1..=10).map(|i| i.to_string()).collect();
+        let mut new = old.clone();
+        new[0] = "one".to_string();
+        new[9] = "ten".to_string();
+
+        let diff = Diff::new(old, new).with_context(ContextSize::Lines(1));
+        let patch = render(&diff.hunks(), &target(1), &target(1));
+        assert_eq!(
+            patch,
+            "--- a/src/lib.rs\n+++ b/src/lib.rs\n\
+             @@ -1,2 +1,2 @@\n-1\n+one\n 2\n\
+             @@ -9,2 +9,2 @@\n 9\n-10\n+ten\n"
+        );
+    }
+
+    #[test]
+    fn patch_without_line_numbers_ignores_position() {
+        let patch = |offset: usize| {
+            let mut old = lines_of(&["impl App {"]);
+            old.extend((0..offset).map(|i| format!("    // {i}")));
+            old.extend(lines_of(&[
+                "fn run() {",
+                "    a();",
+                "    b();",
+                "    c();",
+                "}",
+            ]));
+            let mut new = old.clone();
+            new[offset + 4] = "    d();".to_string();
+            Diff::new(old, new).patch_lines()
+        };
+
+        assert_eq!(patch(1), patch(20));
+        assert_eq!(
+            patch(20),
+            [
+                "@@ impl App {",
+                " fn run() {",
+                "     a();",
+                "     b();",
+                "-    c();",
+                "+    d();",
+                " }",
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_side_uses_preceding_line_number() {
+        let diff = Diff::new(vec![], lines_of(&["a", "b"]));
+        assert_eq!(
+            render(&diff.hunks(), &target(5), &target(5)),
+            "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -4,0 +5,2 @@\n+a\n+b\n"
+        );
+    }
+}
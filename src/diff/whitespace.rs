@@ -0,0 +1,100 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum WhitespaceMode {
+    /// 空白も含めて比較する
+    #[default]
+    None,
+    /// 空白をすべて無視する（git diff -w）
+    IgnoreAll,
+    /// 空白の量の違いを無視する（git diff -b）
+    IgnoreChange,
+    /// 行末の空白を無視する（git diff --ignore-space-at-eol）
+    IgnoreTrailing,
+}
+
+impl WhitespaceMode {
+    /// 行の対応付けに使う比較用の文字列を返す
+    pub fn normalize<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        match self {
+            WhitespaceMode::None => Cow::Borrowed(line),
+            WhitespaceMode::IgnoreAll => {
+                Cow::Owned(line.chars().filter(|c| !c.is_whitespace()).collect())
+            }
+            WhitespaceMode::IgnoreChange => {
+                let mut normalized = String::with_capacity(line.len());
+                let mut in_space = false;
+                for c in line.trim_end().chars() {
+                    if c.is_whitespace() {
+                        in_space = true;
+                        continue;
+                    }
+                    if in_space {
+                        normalized.push(' ');
+                        in_space = false;
+                    }
+                    normalized.push(c);
+                }
+                Cow::Owned(normalized)
+            }
+            WhitespaceMode::IgnoreTrailing => Cow::Borrowed(line.trim_end()),
+        }
+    }
+}
+
+impl fmt::Display for WhitespaceMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            WhitespaceMode::None => "none",
+            WhitespaceMode::IgnoreAll => "ignore-all",
+            WhitespaceMode::IgnoreChange => "ignore-change",
+            WhitespaceMode::IgnoreTrailing => "ignore-trailing",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+impl FromStr for WhitespaceMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true).map_err(|_| format!("不明な空白の扱いです: {}", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignore_all_removes_every_space() {
+        assert_eq!(WhitespaceMode::IgnoreAll.normalize(" a \t b "), "ab");
+    }
+
+    #[test]
+    fn ignore_change_collapses_runs() {
+        assert_eq!(WhitespaceMode::IgnoreChange.normalize("  a \t b  "), " a b");
+        assert_ne!(
+            WhitespaceMode::IgnoreChange.normalize("ab"),
+            WhitespaceMode::IgnoreChange.normalize("a b")
+        );
+    }
+
+    #[test]
+    fn ignore_trailing_keeps_indent() {
+        assert_eq!(WhitespaceMode::IgnoreTrailing.normalize("  a  "), "  a");
+    }
+
+    #[test]
+    fn from_str_accepts_value_names() {
+        assert_eq!(
+            "ignore-change".parse::<WhitespaceMode>(),
+            Ok(WhitespaceMode::IgnoreChange)
+        );
+        assert!("tabs".parse::<WhitespaceMode>().is_err());
+    }
+}
@@ -1,7 +1,8 @@
+use crate::git::encoding::{self, EncodingRules, TextContent};
 use crate::git::error::GitError;
 use clap::ValueEnum;
-use git2::{DiffOptions, Repository};
-use std::path::PathBuf;
+use git2::{AttrCheckFlags, AttrValue, DiffOptions, ObjectType, Oid, Repository};
+use std::path::{Path, PathBuf};
 
 #[cfg(test)]
 use mockall::automock;
@@ -18,6 +19,38 @@ pub enum SourceKind {
     Worktree,
 }
 
+/// git と同じく、先頭からこのバイト数までに NUL があればバイナリとみなす
+const BINARY_CHECK_BYTES: usize = 8000;
+
+/// ファイルの中身を取り出さずに分かる情報
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct BlobInfo {
+    pub oid: String,
+    pub size: usize,
+    /// NUL バイトを含むか、.gitattributes で binary (-diff) が指定されている
+    pub binary: bool,
+}
+
+/// リビジョンを解決したコミット
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct CommitInfo {
+    pub sha: String,
+    /// コミットメッセージの 1 行目
+    pub subject: String,
+}
+
+impl CommitInfo {
+    pub fn short_sha(&self) -> &str {
+        &self.sha[..self.sha.len().min(7)]
+    }
+}
+
+impl std::fmt::Display for CommitInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.short_sha(), self.subject)
+    }
+}
+
 #[cfg_attr(test, automock)]
 pub trait GitProvider {
     fn get_current_branch(&self) -> Result<String, GitError>;
@@ -30,19 +63,45 @@ pub trait GitProvider {
         end: usize,
         source: Option<SourceKind>,
     ) -> Result<Vec<String>, GitError>;
+    /// ファイル全体を、判定した文字コードと改行コードとともに取り出す
+    fn extract_text(
+        &self,
+        branch: &str,
+        file_path: &str,
+        source: Option<SourceKind>,
+    ) -> Result<TextContent, GitError>;
     fn list_changed_files(
         &self,
         base_branch: &str,
         target_branch: &str,
     ) -> Result<Vec<String>, GitError>;
+    fn blob_info(
+        &self,
+        branch: &str,
+        file_path: &str,
+        source: Option<SourceKind>,
+    ) -> Result<BlobInfo, GitError>;
+    /// 2 つのブランチが分岐したコミット (マージベース) の SHA を返す
+    fn merge_base(&self, base_branch: &str, target_branch: &str) -> Result<String, GitError>;
+    /// ブランチ名のほか、タグ・SHA・HEAD~N・stash@{0} など git rev-parse が受け付けるリビジョンをコミットに解決する
+    fn resolve_revision(&self, revision: &str) -> Result<CommitInfo, GitError>;
     fn is_managed(&self) -> Result<bool, GitError>;
 }
 
-pub struct Git;
+pub struct Git {
+    encodings: EncodingRules,
+}
 
 impl Git {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            encodings: EncodingRules::default(),
+        }
+    }
+
+    pub fn with_encodings(mut self, encodings: EncodingRules) -> Self {
+        self.encodings = encodings;
+        self
     }
 
     fn extract_lines_from_string(
@@ -59,8 +118,7 @@ impl Git {
 
         let count = end - start_index + 1;
 
-        Ok(content
-            .lines()
+        Ok(encoding::split_lines(content)
             .skip(start_index - 1)
             .take(count)
             .map(|s| s.to_string())
@@ -68,6 +126,64 @@ impl Git {
     }
 }
 
+impl Git {
+    fn read_content(
+        &self,
+        repo: &Repository,
+        branch: &str,
+        file_path: &str,
+        source: SourceKind,
+    ) -> Result<Vec<u8>, GitError> {
+        match source {
+            SourceKind::Worktree => std::fs::read(file_path).map_err(|_| GitError::FileNotFound),
+            SourceKind::Commit => {
+                let spec = format!("{branch}:{file_path}");
+                let object = repo
+                    .revparse_single(&spec)
+                    .map_err(|_| GitError::FileNotFound)?;
+                let blob = object.as_blob().ok_or(GitError::InvalidObjectType)?;
+                Ok(blob.content().to_vec())
+            }
+        }
+    }
+
+    fn is_binary(&self, repo: &Repository, file_path: &str, content: &[u8]) -> bool {
+        is_binary_content(content) || is_binary_by_attributes(repo, file_path)
+    }
+
+    /// バイナリでなければ、パスごとの文字コードの設定に従って文字列にする
+    fn read_text(
+        &self,
+        branch: &str,
+        file_path: &str,
+        source: Option<SourceKind>,
+    ) -> Result<(String, encoding::TextInfo), GitError> {
+        let repo = Repository::open(".").map_err(|_| GitError::NotGitManaged)?;
+        let source = source.unwrap_or(SourceKind::Commit);
+        let content = self.read_content(&repo, branch, file_path, source)?;
+        if self.is_binary(&repo, file_path, &content) {
+            return Err(GitError::BinaryFile);
+        }
+        self.encodings.decode(file_path, &content)
+    }
+}
+
+fn is_binary_content(content: &[u8]) -> bool {
+    content
+        .iter()
+        .take(BINARY_CHECK_BYTES)
+        .any(|&byte| byte == 0)
+}
+
+/// `binary` は `-diff -merge -text` のマクロなので、`diff` が外されている場合もバイナリとして扱う
+fn is_binary_by_attributes(repo: &Repository, file_path: &str) -> bool {
+    let attr = |name| {
+        repo.get_attr(Path::new(file_path), name, AttrCheckFlags::default())
+            .map(AttrValue::from_string)
+    };
+    matches!(attr("binary"), Ok(AttrValue::True)) || matches!(attr("diff"), Ok(AttrValue::False))
+}
+
 impl GitProvider for Git {
     fn get_current_branch(&self) -> Result<String, GitError> {
         Repository::open(".")
@@ -102,25 +218,18 @@ impl GitProvider for Git {
         end: usize,
         source: Option<SourceKind>,
     ) -> Result<Vec<String>, GitError> {
-        let source = source.unwrap_or(SourceKind::Commit);
-        match source {
-            SourceKind::Worktree => {
-                let content =
-                    std::fs::read_to_string(file_path).map_err(|_| GitError::FileNotFound)?;
-                self.extract_lines_from_string(&content, start, end)
-            }
-            SourceKind::Commit => {
-                let repo = Repository::open(".").map_err(|_| GitError::NotGitManaged)?;
-                let spec = format!("{branch}:{file_path}");
-                let object = repo
-                    .revparse_single(&spec)
-                    .map_err(|_| GitError::FileNotFound)?;
-                let blob = object.as_blob().ok_or(GitError::InvalidObjectType)?;
-                let content =
-                    std::str::from_utf8(blob.content()).map_err(|_| GitError::InvalidUtf8)?;
-                self.extract_lines_from_string(content, start, end)
-            }
-        }
+        let (content, _) = self.read_text(branch, file_path, source)?;
+        self.extract_lines_from_string(&content, start, end)
+    }
+
+    fn extract_text(
+        &self,
+        branch: &str,
+        file_path: &str,
+        source: Option<SourceKind>,
+    ) -> Result<TextContent, GitError> {
+        let (content, info) = self.read_text(branch, file_path, source)?;
+        Ok(TextContent::new(&content, info))
     }
 
     fn list_changed_files(
@@ -193,6 +302,54 @@ impl GitProvider for Git {
             .collect())
     }
 
+    fn blob_info(
+        &self,
+        branch: &str,
+        file_path: &str,
+        source: Option<SourceKind>,
+    ) -> Result<BlobInfo, GitError> {
+        let repo = Repository::open(".").map_err(|_| GitError::NotGitManaged)?;
+        let source = source.unwrap_or(SourceKind::Commit);
+        let content = self.read_content(&repo, branch, file_path, source)?;
+        let oid = Oid::hash_object(ObjectType::Blob, &content)
+            .map_err(|_| GitError::InvalidObjectType)?;
+
+        Ok(BlobInfo {
+            oid: oid.to_string(),
+            size: content.len(),
+            binary: self.is_binary(&repo, file_path, &content),
+        })
+    }
+
+    fn merge_base(&self, base_branch: &str, target_branch: &str) -> Result<String, GitError> {
+        let repo = Repository::open(".").map_err(|_| GitError::NotGitManaged)?;
+        let commit_id = |branch: &str| {
+            repo.revparse_single(branch)
+                .and_then(|object| object.peel_to_commit())
+                .map(|commit| commit.id())
+                .map_err(|_| GitError::FileNotFound)
+        };
+
+        let oid = repo
+            .merge_base(commit_id(base_branch)?, commit_id(target_branch)?)
+            .map_err(|_| GitError::MergeBaseNotFound)?;
+        Ok(oid.to_string())
+    }
+
+    fn resolve_revision(&self, revision: &str) -> Result<CommitInfo, GitError> {
+        let repo = Repository::open(".").map_err(|_| GitError::NotGitManaged)?;
+        let commit = repo
+            .revparse_single(revision)
+            .map_err(|_| GitError::RevisionNotFound(revision.to_string()))?
+            .peel_to_commit()
+            .map_err(|_| GitError::NotACommit(revision.to_string()))?;
+
+        Ok(CommitInfo {
+            sha: commit.id().to_string(),
+            subject: commit.summary().unwrap_or_default().to_string(),
+        })
+    }
+
     fn is_managed(&self) -> Result<bool, GitError> {
         Repository::open(".")
             .map(|_| true)
@@ -204,6 +361,26 @@ impl GitProvider for Git {
 mod tests {
     use super::*;
 
+    #[test]
+    fn detects_nul_bytes_as_binary() {
+        assert!(is_binary_content(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        assert!(!is_binary_content("テキスト\nfoo".as_bytes()));
+
+        let mut late_nul = vec![b'a'; BINARY_CHECK_BYTES];
+        late_nul.push(0);
+        assert!(!is_binary_content(&late_nul));
+    }
+
+    #[test]
+    fn commit_info_shows_short_sha_and_subject() {
+        let commit = CommitInfo {
+            sha: "1a2b3c4d5e6f7a8b9c0d1a2b3c4d5e6f7a8b9c0d".to_string(),
+            subject: "Release 1.2.0".to_string(),
+        };
+
+        assert_eq!(commit.to_string(), "1a2b3c4 Release 1.2.0");
+    }
+
     #[test]
     fn normal_range() {
         let git = Git::new();
@@ -241,6 +418,13 @@ mod tests {
         assert_eq!(result, vec!["two".to_string()]);
     }
 
+    #[test]
+    fn crlf_is_kept() {
+        let git = Git::new();
+        let result = git.extract_lines_from_string("a\r\nb\r\n", 1, 5).unwrap();
+        assert_eq!(result, vec!["a\r".to_string(), "b\r".to_string()]);
+    }
+
     #[test]
     fn empty_content() {
         let git = Git::new();
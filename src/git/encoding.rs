@@ -0,0 +1,205 @@
+use chardetng::{EncodingDetector, Iso2022JpDetection, Utf8Detection};
+use encoding_rs::{Encoding, UTF_8};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use indexmap::IndexMap;
+
+use crate::git::error::GitError;
+
+/// パスの glob ごとに指定された文字コード。どれにも当たらないファイルは自動判定する
+#[derive(Default)]
+pub struct EncodingRules {
+    globs: GlobSet,
+    encodings: Vec<&'static Encoding>,
+}
+
+impl EncodingRules {
+    /// 設定ファイルの `encodings` (glob → 文字コード名) から作る。先に書いた glob ほど優先する
+    pub fn new(rules: &IndexMap<String, String>) -> Result<Self, GitError> {
+        let mut globs = GlobSetBuilder::new();
+        let mut encodings = Vec::with_capacity(rules.len());
+
+        for (pattern, label) in rules {
+            let glob =
+                Glob::new(pattern).map_err(|_| GitError::InvalidPathPattern(pattern.clone()))?;
+            let encoding = Encoding::for_label(label.trim().as_bytes())
+                .ok_or_else(|| GitError::UnknownEncoding(label.clone()))?;
+            globs.add(glob);
+            encodings.push(encoding);
+        }
+
+        let globs = globs
+            .build()
+            .map_err(|e| GitError::InvalidPathPattern(e.to_string()))?;
+        Ok(Self { globs, encodings })
+    }
+
+    fn encoding_for(&self, file_path: &str) -> Option<&'static Encoding> {
+        self.globs
+            .matches(file_path)
+            .into_iter()
+            .min()
+            .map(|index| self.encodings[index])
+    }
+
+    /// 指定された文字コード、なければ BOM・UTF-8・推定の順で判定して文字列にする
+    pub fn decode(&self, file_path: &str, content: &[u8]) -> Result<(String, TextInfo), GitError> {
+        let encoding = self
+            .encoding_for(file_path)
+            .unwrap_or_else(|| detect(content));
+
+        let (text, encoding, had_errors) = encoding.decode(content);
+        if had_errors {
+            return Err(GitError::InvalidEncoding(encoding.name().to_string()));
+        }
+
+        let info = TextInfo {
+            encoding: encoding.name().to_string(),
+            eol: LineEnding::detect(&text),
+        };
+        Ok((text.into_owned(), info))
+    }
+}
+
+fn detect(content: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(content) {
+        return encoding;
+    }
+    if std::str::from_utf8(content).is_ok() {
+        return UTF_8;
+    }
+
+    // 日本語のファイルを想定し、Shift_JIS / EUC-JP を優先して推定する
+    let mut detector = EncodingDetector::new(Iso2022JpDetection::Allow);
+    detector.feed(content, true);
+    detector.guess(Some(b"jp"), Utf8Detection::Allow)
+}
+
+/// ファイルの改行コード
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    /// LF と CRLF が混在している
+    Mixed,
+    /// 改行を含まない
+    None,
+}
+
+impl LineEnding {
+    fn detect(text: &str) -> Self {
+        let lines = text.matches('\n').count();
+        let crlf = text.matches("\r\n").count();
+        match (lines, crlf) {
+            (0, _) => LineEnding::None,
+            (lines, crlf) if lines == crlf => LineEnding::Crlf,
+            (_, 0) => LineEnding::Lf,
+            _ => LineEnding::Mixed,
+        }
+    }
+}
+
+/// 取り出したテキストの文字コードと改行コード
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct TextInfo {
+    pub encoding: String,
+    pub eol: LineEnding,
+}
+
+/// ファイル全体の行。CRLF の `\r` は行の末尾に残し、比較で無視するかは `--ignore-cr-at-eol` で決める
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextContent {
+    pub lines: Vec<String>,
+    pub info: TextInfo,
+}
+
+impl TextContent {
+    pub fn new(text: &str, info: TextInfo) -> Self {
+        let lines = split_lines(text).map(|line| line.to_string()).collect();
+        Self { lines, info }
+    }
+}
+
+/// `\n` で区切った行。`str::lines` と異なり、CRLF の `\r` は取り除かない
+pub fn split_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.split_inclusive('\n')
+        .map(|line| line.strip_suffix('\n').unwrap_or(line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(pairs: &[(&str, &str)]) -> EncodingRules {
+        let map = pairs
+            .iter()
+            .map(|(glob, label)| (glob.to_string(), label.to_string()))
+            .collect();
+        EncodingRules::new(&map).unwrap()
+    }
+
+    #[test]
+    fn detects_japanese_legacy_encodings() {
+        let text = "日本語のテキストです。文字コードを推定します。\n";
+        let rules = EncodingRules::default();
+
+        for encoding in [encoding_rs::SHIFT_JIS, encoding_rs::EUC_JP] {
+            let (bytes, _, _) = encoding.encode(text);
+            let (decoded, info) = rules.decode("a.txt", &bytes).unwrap();
+            assert_eq!(decoded, text);
+            assert_eq!(info.encoding, encoding.name());
+        }
+
+        let (_, info) = rules.decode("a.txt", text.as_bytes()).unwrap();
+        assert_eq!(info.encoding, "UTF-8");
+    }
+
+    #[test]
+    fn configured_encoding_wins_for_matching_paths() {
+        let rules = rules(&[("legacy/**/*.txt", "sjis"), ("**/*.txt", "euc-jp")]);
+        let (bytes, _, _) = encoding_rs::SHIFT_JIS.encode("設定");
+
+        let (decoded, info) = rules.decode("legacy/doc/a.txt", &bytes).unwrap();
+        assert_eq!(decoded, "設定");
+        assert_eq!(info.encoding, "Shift_JIS");
+
+        assert_eq!(
+            rules.decode("other/a.txt", &bytes),
+            Err(GitError::InvalidEncoding("EUC-JP".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_encoding_and_bad_glob() {
+        let map = IndexMap::from([("*.txt".to_string(), "klingon".to_string())]);
+        assert!(matches!(
+            EncodingRules::new(&map),
+            Err(GitError::UnknownEncoding(_))
+        ));
+
+        let map = IndexMap::from([("[".to_string(), "utf-8".to_string())]);
+        assert!(matches!(
+            EncodingRules::new(&map),
+            Err(GitError::InvalidPathPattern(_))
+        ));
+    }
+
+    #[test]
+    fn detects_line_endings() {
+        assert_eq!(LineEnding::detect("a\nb\n"), LineEnding::Lf);
+        assert_eq!(LineEnding::detect("a\r\nb\r\n"), LineEnding::Crlf);
+        assert_eq!(LineEnding::detect("a\r\nb\n"), LineEnding::Mixed);
+        assert_eq!(LineEnding::detect("a"), LineEnding::None);
+    }
+
+    #[test]
+    fn crlf_is_kept_in_lines() {
+        let info = TextInfo {
+            encoding: "UTF-8".to_string(),
+            eol: LineEnding::Crlf,
+        };
+        let content = TextContent::new("a\r\nb\r\nc", info);
+        assert_eq!(content.lines, vec!["a\r", "b\r", "c"]);
+        assert_eq!(content.info.eol, LineEnding::Crlf);
+    }
+}
@@ -4,8 +4,14 @@ pub enum GitError {
     NotGitManaged,
     FileNotFound,
     InvalidObjectType,
-    InvalidUtf8,
+    InvalidEncoding(String),
+    UnknownEncoding(String),
+    InvalidPathPattern(String),
+    BinaryFile,
+    MergeBaseNotFound,
     DiffExtractionFailed,
+    RevisionNotFound(String),
+    NotACommit(String),
 }
 
 impl std::fmt::Display for GitError {
@@ -15,8 +21,29 @@ impl std::fmt::Display for GitError {
             GitError::NotGitManaged => write!(f, "Git 管理されていないディレクトリです"),
             GitError::FileNotFound => write!(f, "ファイルが見つかりません"),
             GitError::InvalidObjectType => write!(f, "無効なオブジェクトタイプです"),
-            GitError::InvalidUtf8 => write!(f, "無効な UTF-8 文字列です"),
+            GitError::InvalidEncoding(encoding) => {
+                write!(f, "{} の文字列として読み込めませんでした", encoding)
+            }
+            GitError::UnknownEncoding(label) => write!(f, "不明な文字コードです: {}", label),
+            GitError::InvalidPathPattern(pattern) => {
+                write!(f, "パスのパターンが不正です: {}", pattern)
+            }
+            GitError::BinaryFile => write!(f, "バイナリファイルのため行を取り出せません"),
+            GitError::MergeBaseNotFound => {
+                write!(
+                    f,
+                    "2 つのブランチの共通の祖先となるコミットが見つかりません"
+                )
+            }
             GitError::DiffExtractionFailed => write!(f, "差分の抽出に失敗しました"),
+            GitError::RevisionNotFound(revision) => write!(
+                f,
+                "'{}' はブランチ・タグ・コミットのいずれとしても見つかりません",
+                revision
+            ),
+            GitError::NotACommit(revision) => {
+                write!(f, "'{}' はコミットを指していません", revision)
+            }
         }
     }
 }
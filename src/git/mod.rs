@@ -1,7 +1,9 @@
 pub mod core;
+mod encoding;
 mod error;
 
 pub use core::*;
+pub use encoding::*;
 pub use error::*;
 
 pub fn init() -> Result<Git, error::GitError> {
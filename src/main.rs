@@ -10,6 +10,7 @@ use commands::{Commands, handle_cli_command, handle_web_command};
 use std::process;
 use std::sync::Arc;
 
+use config::Manager;
 use git::GitProvider;
 
 #[derive(Parser)]
@@ -31,10 +32,17 @@ fn main() {
         process::exit(1);
     });
 
-    let git = git::init().unwrap_or_else(|err| {
-        eprintln!("エラー: {}", err);
-        process::exit(1);
-    });
+    // 設定ファイルがまだない場合は、すべてのファイルの文字コードを自動判定する
+    let encodings = config
+        .load()
+        .map(|data| data.encodings())
+        .unwrap_or_default();
+    let git = git::EncodingRules::new(&encodings)
+        .and_then(|rules| git::init().map(|git| git.with_encodings(rules)))
+        .unwrap_or_else(|err| {
+            eprintln!("エラー: {}", err);
+            process::exit(1);
+        });
 
     match cli.command {
         Commands::Cli(command) => {
@@ -1 +1,3 @@
 pub mod env;
+#[cfg(test)]
+pub mod test_support;
@@ -0,0 +1,20 @@
+use crate::git::{GitError, LineEnding, TextContent, TextInfo};
+
+/// 文字列の配列を、比較に渡す行の並びにする
+pub fn lines_of(text: &[&str]) -> Vec<String> {
+    text.iter().map(|s| s.to_string()).collect()
+}
+
+/// UTF-8・LF のファイルとして読み出した内容。`extract_text` のモックの戻り値に使う
+pub fn text_of(lines: &[&str]) -> Result<TextContent, GitError> {
+    text_in(&lines.join("\n"), "UTF-8", LineEnding::Lf)
+}
+
+/// 文字コードと改行コードを指定して読み出した内容
+pub fn text_in(text: &str, encoding: &str, eol: LineEnding) -> Result<TextContent, GitError> {
+    let info = TextInfo {
+        encoding: encoding.to_string(),
+        eol,
+    };
+    Ok(TextContent::new(text, info))
+}
@@ -45,6 +45,7 @@ const numberCell = tv({
       added: "bg-green-300",
       removed: "bg-red-300",
       replaced: "bg-yellow-300",
+      moved: "bg-purple-300",
       equal: "",
       null: "bg-gray-100",
     },
@@ -63,6 +64,7 @@ const diffCell = tv({
       added: "bg-green-100",
       removed: "bg-red-100",
       replaced: "bg-yellow-100",
+      moved: "bg-purple-100",
       equal: "",
       null: "bg-gray-100",
     },
@@ -37,3 +37,25 @@ export function DiffViewerNoData({ fileName }: DiffViewerFallbacksProps) {
     </ViewerBox>
   );
 }
+
+interface DiffViewerBinaryProps extends DiffViewerFallbacksProps {
+  oldSize: number | null;
+  newSize: number | null;
+}
+
+export function DiffViewerBinary({
+  fileName,
+  oldSize,
+  newSize,
+}: DiffViewerBinaryProps) {
+  const format = (size: number | null) => (size === null ? "-" : `${size}`);
+  return (
+    <ViewerBox fileName={fileName}>
+      <div className="flex items-center justify-center h-32">
+        <span className="text-gray-500">
+          Binary file: {format(oldSize)} → {format(newSize)} bytes
+        </span>
+      </div>
+    </ViewerBox>
+  );
+}
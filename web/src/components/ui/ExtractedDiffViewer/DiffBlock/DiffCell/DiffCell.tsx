@@ -8,6 +8,7 @@ const diffCellContent = tv({
       added: "bg-green-200 text-green-900 mx-[1px] px-[2px]",
       removed: "bg-red-200 text-red-900 line-through mx-[1px] px-[2px]",
       replaced: "",
+      moved: "",
       equal: "",
     },
   },
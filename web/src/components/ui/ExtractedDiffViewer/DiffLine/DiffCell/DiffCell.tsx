@@ -10,6 +10,7 @@ const diffCell = tv({
       added: "bg-green-100",
       removed: "bg-red-100",
       replaced: "bg-yellow-100",
+      moved: "bg-purple-100",
       equal: "",
     },
   },
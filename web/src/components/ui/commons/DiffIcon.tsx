@@ -8,6 +8,7 @@ const diffIcon = tv({
       added: "text-green-500",
       removed: "text-red-500",
       replaced: "text-yellow-500",
+      moved: "text-purple-500",
       equal: "",
     },
   },
@@ -24,6 +25,8 @@ export function DiffIcon({ diffType }: { diffType: DiffType }) {
       return <span className={diffIcon({ diffType })}>-</span>;
     case "replaced":
       return <span className={diffIcon({ diffType })}>~</span>;
+    case "moved":
+      return <span className={diffIcon({ diffType })}>↕</span>;
     default:
       return <span className={diffIcon({ diffType })}>&nbsp;</span>;
   }
@@ -2,7 +2,42 @@ import useSWR from "swr";
 import { z } from "zod";
 import { fetcher } from "@/lib";
 
-const filesDataSchema = z.array(z.string());
+const blobInfoSchema = z.object({
+  oid: z.string(),
+  size: z.number(),
+  binary: z.boolean(),
+});
+
+const textInfoSchema = z.object({
+  encoding: z.string(),
+  eol: z.enum(["lf", "crlf", "mixed", "none"]),
+});
+
+const filesDataSchema = z.array(
+  z.object({
+    path: z.string(),
+    stats: z
+      .object({
+        added: z.number(),
+        removed: z.number(),
+        replaced: z.number(),
+        similarity: z.number(),
+      })
+      .nullable(),
+    text: z
+      .object({
+        old: textInfoSchema.nullable(),
+        new: textInfoSchema.nullable(),
+      })
+      .nullable(),
+    binary: z
+      .object({
+        old: blobInfoSchema.nullable(),
+        new: blobInfoSchema.nullable(),
+      })
+      .nullable(),
+  }),
+);
 type FilesData = z.infer<typeof filesDataSchema>;
 
 export const useFilesData = () => {
@@ -1,5 +1,5 @@
 import { ReactElement, useMemo } from "react";
-import { DiffViewer } from "@/components/ui";
+import { DiffViewer, DiffViewerBinary } from "@/components/ui";
 import { useFilesData } from "@/hooks";
 import { extractExtension } from "@/utils";
 import {
@@ -13,7 +13,7 @@ export function MainPage(): ReactElement {
 
   const extensions = useMemo(() => {
     if (!data) return [];
-    const fileNames = Object.keys(data);
+    const fileNames = data.map((file) => file.path);
     if (!fileNames) return [];
 
     const extSet = new Set<string>();
@@ -34,9 +34,18 @@ export function MainPage(): ReactElement {
       <SelectedDiffValueProvider>
         <DiffViewer.Provider extensions={extensions}>
           <div className="flex flex-col gap-4 p-4">
-            {data.map((fileName, index) => (
-              <DiffViewerContainer key={index} fileName={fileName} />
-            ))}
+            {data.map((file) =>
+              file.binary ? (
+                <DiffViewerBinary
+                  key={file.path}
+                  fileName={file.path}
+                  oldSize={file.binary.old?.size ?? null}
+                  newSize={file.binary.new?.size ?? null}
+                />
+              ) : (
+                <DiffViewerContainer key={file.path} fileName={file.path} />
+              ),
+            )}
           </div>
         </DiffViewer.Provider>
         <ExtractedDiffViewerContainer />
@@ -1,4 +1,10 @@
 import { z } from "zod";
 
-export const diffTypeSchema = z.enum(["added", "removed", "equal", "replaced"]);
+export const diffTypeSchema = z.enum([
+  "added",
+  "removed",
+  "equal",
+  "replaced",
+  "moved",
+]);
 export type DiffType = z.infer<typeof diffTypeSchema>;